@@ -0,0 +1,110 @@
+use bili_live::{Area, AreaListFetch, AreaParent, BilibiliClient, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use crate::{user_info, user_warning};
+
+pub const AREA_CACHE_FILE: &str = "areas.json";
+
+/// 分区列表缓存的默认有效期：一天
+pub const DEFAULT_AREA_TTL: u64 = 24 * 60 * 60;
+
+/// 缓存在状态目录中的分区列表
+#[derive(Debug, Serialize, Deserialize)]
+struct AreaCache {
+    fetched_at: DateTime<Local>,
+    #[serde(default)]
+    etag: Option<String>,
+    areas: Vec<AreaParent>,
+}
+
+impl AreaCache {
+    fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(cache) => Some(cache),
+            Err(e) => {
+                user_warning!("分区缓存 {} 已损坏: {}，将重新获取", path.display(), e);
+                None
+            }
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    fn is_fresh(&self, ttl: u64) -> bool {
+        let age = Local::now() - self.fetched_at;
+        age >= chrono::Duration::zero() && age.num_seconds() < i64::try_from(ttl).unwrap_or(i64::MAX)
+    }
+}
+
+/// 读取分区列表：缓存未过期时直接使用，过期后携带 ETag 重新获取，获取失败时退回到过期的缓存
+pub fn load(client: &BilibiliClient, path: &Path, ttl: u64, refresh: bool) -> Result<Vec<AreaParent>> {
+    let cache = AreaCache::load(path);
+    if let Some(cache) = &cache
+        && !refresh
+        && cache.is_fresh(ttl)
+    {
+        return Ok(cache.areas.clone());
+    }
+
+    let etag = cache.as_ref().and_then(|c| c.etag.as_deref());
+    let fetched = match client.area_list_if_changed(etag) {
+        Ok(fetched) => fetched,
+        Err(e) => match cache {
+            Some(cache) => {
+                user_warning!("获取分区列表失败: {}，使用 {} 缓存的分区列表", e, cache.fetched_at.format("%Y-%m-%d %H:%M:%S"));
+                return Ok(cache.areas);
+            }
+            None => return Err(e),
+        },
+    };
+
+    let cache = match (fetched, cache) {
+        (AreaListFetch::NotModified, Some(cache)) => {
+            user_info!("分区列表没有变化，继续使用缓存");
+            AreaCache { fetched_at: Local::now(), ..cache }
+        }
+        (AreaListFetch::NotModified, None) => return client.area_list(),
+        (AreaListFetch::Updated { areas, etag }, _) => AreaCache { fetched_at: Local::now(), etag, areas },
+    };
+    if let Err(e) = cache.save(path) {
+        user_warning!("保存分区缓存失败: {}", e);
+    }
+    Ok(cache.areas)
+}
+
+/// 按二级分区ID查找分区及其所属的一级分区
+pub fn find(areas: &[AreaParent], id: u32) -> Option<(&AreaParent, &Area)> {
+    areas
+        .iter()
+        .find_map(|parent| parent.list.iter().find(|area| area.id == id).map(|area| (parent, area)))
+}
+
+/// 按关键字搜索分区，不区分大小写；一级分区名称匹配时保留其全部二级分区
+pub fn search(areas: &[AreaParent], keyword: &str) -> Vec<AreaParent> {
+    let keyword = keyword.trim().to_lowercase();
+    areas
+        .iter()
+        .filter_map(|parent| {
+            let list: Vec<Area> = if parent.name.to_lowercase().contains(&keyword) {
+                parent.list.clone()
+            } else {
+                parent
+                    .list
+                    .iter()
+                    .filter(|area| area.name.to_lowercase().contains(&keyword) || area.id.to_string() == keyword)
+                    .cloned()
+                    .collect()
+            };
+            (!list.is_empty()).then(|| AreaParent { name: parent.name.clone(), list })
+        })
+        .collect()
+}
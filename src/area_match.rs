@@ -0,0 +1,81 @@
+use bili_live::{Area, AreaParent};
+use pinyin::ToPinyin;
+
+/// 分区名称的小写形式及其拼音，非汉字按原样保留
+struct Keys {
+    name: String,
+    initials: String,
+    pinyin: String,
+}
+
+impl Keys {
+    fn new(name: &str) -> Self {
+        let name = name.to_lowercase();
+        let mut initials = String::new();
+        let mut pinyin = String::new();
+        for (c, py) in name.chars().zip(name.as_str().to_pinyin()) {
+            match py {
+                Some(py) => {
+                    initials.push_str(py.first_letter());
+                    pinyin.push_str(py.plain());
+                }
+                None if c.is_whitespace() => {}
+                None => {
+                    initials.push(c);
+                    pinyin.push(c);
+                }
+            }
+        }
+        Self { name, initials, pinyin }
+    }
+}
+
+/// `needle` 的字符是否按顺序出现在 `haystack` 中
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut chars = haystack.chars();
+    needle.chars().all(|c| chars.any(|h| h == c))
+}
+
+/// 匹配程度，越大越靠前，不匹配时为 0
+fn score(query: &str, parent: &Keys, area: &Area, keys: &Keys) -> u32 {
+    if area.id.to_string() == query || keys.name == query {
+        100
+    } else if keys.name.starts_with(query) {
+        80
+    } else if keys.name.contains(query) {
+        60
+    } else if keys.initials.starts_with(query) {
+        50
+    } else if keys.pinyin.starts_with(query) {
+        45
+    } else if keys.initials.contains(query) || keys.pinyin.contains(query) {
+        40
+    } else if is_subsequence(query, &keys.name) || is_subsequence(query, &keys.initials) {
+        20
+    } else if parent.name.contains(query) || parent.initials.starts_with(query) || parent.pinyin.starts_with(query) {
+        10
+    } else {
+        0
+    }
+}
+
+/// 在全部一级分区下按名称、拼音首字母或全拼搜索二级分区，按匹配程度排序
+pub fn rank<'a>(areas: &'a [AreaParent], query: &str) -> Vec<(&'a AreaParent, &'a Area)> {
+    let query: String = query.trim().to_lowercase().split_whitespace().collect();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let mut matches = Vec::new();
+    for parent in areas {
+        let parent_keys = Keys::new(&parent.name);
+        for area in &parent.list {
+            let score = score(&query, &parent_keys, area, &Keys::new(&area.name));
+            if score > 0 {
+                matches.push((score, parent, area));
+            }
+        }
+    }
+    // 同分时名称较短的更接近，再按原有顺序
+    matches.sort_by_key(|(score, _, area)| (std::cmp::Reverse(*score), area.name.chars().count()));
+    matches.into_iter().map(|(_, parent, area)| (parent, area)).collect()
+}
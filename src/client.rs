@@ -0,0 +1,485 @@
+use std::time::Duration;
+use ureq::http::{self, header, request::Builder};
+use crate::cookies::Cookies;
+use crate::error::{BiliLiveError, Result};
+use crate::refresh;
+use crate::types::{
+    ApiResponse, AreaListFetch, AreaParent, CookieInfoData, NavData, QrCodeData, QrPollData, RecentArea,
+    RefreshData, RoomDetailData, RoomInfoData, RoomNewsData, SpiData, StartLiveData, StopLiveData,
+    UploadImageData,
+};
+
+/// 单个请求的默认超时时间
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/130.0.0.0 Safari/537.36 Edg/130.0.0.0";
+
+/// 各类接口所在的服务地址
+#[derive(Debug, Clone)]
+pub struct ApiBase {
+    /// 登录相关接口，如 `https://passport.bilibili.com`
+    pub passport: String,
+    /// 主站接口，如 `https://api.bilibili.com`
+    pub web: String,
+    /// 直播接口，如 `https://api.live.bilibili.com`
+    pub live: String,
+    /// 主站页面，如 `https://www.bilibili.com`，刷新登录凭据时使用
+    pub www: String,
+}
+
+impl Default for ApiBase {
+    fn default() -> Self {
+        Self {
+            passport: "https://passport.bilibili.com".to_string(),
+            web: "https://api.bilibili.com".to_string(),
+            live: "https://api.live.bilibili.com".to_string(),
+            www: "https://www.bilibili.com".to_string(),
+        }
+    }
+}
+
+/// B站接口客户端，持有登录凭据、服务地址与公共请求头
+#[derive(Clone)]
+pub struct BilibiliClient {
+    agent: ureq::Agent,
+    base: ApiBase,
+    user_agent: String,
+    cookies: Option<Cookies>,
+}
+
+impl BilibiliClient {
+    pub fn new(base: ApiBase) -> Self {
+        Self {
+            agent: agent(DEFAULT_TIMEOUT),
+            base,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            cookies: None,
+        }
+    }
+
+    /// 设置单个请求的超时时间
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.agent = agent(timeout);
+        self
+    }
+
+    /// 设置登录凭据
+    pub fn set_cookies(&mut self, cookies: Cookies) {
+        self.cookies = Some(cookies);
+    }
+
+    /// 当前的登录凭据
+    pub fn cookies(&self) -> Result<&Cookies> {
+        self.cookies.as_ref().ok_or(BiliLiveError::CookieError)
+    }
+
+    fn get(&self, url: &str) -> Builder {
+        http::Request::get(url).header(header::USER_AGENT, &self.user_agent)
+    }
+
+    fn post_form(&self, url: &str) -> Builder {
+        http::Request::post(url)
+            .header(header::USER_AGENT, &self.user_agent)
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+    }
+
+    /// 附加登录凭据
+    fn authorized(&self, request: Builder) -> Result<Builder> {
+        let cookies = self.cookies()?;
+        Ok(request.header(header::COOKIE, cookies.header()))
+    }
+
+    /// 未附加登录凭据的请求也带上已知的设备标识
+    fn execute(&self, mut request: Builder, body: impl Into<Vec<u8>>) -> Result<http::Response<ureq::Body>> {
+        let device_header = self.cookies.as_ref().map(Cookies::device_header).unwrap_or_default();
+        if let Some(headers) = request.headers_mut()
+            && !headers.contains_key(header::COOKIE)
+            && let Ok(value) = device_header.parse()
+            && !device_header.is_empty()
+        {
+            headers.insert(header::COOKIE, value);
+        }
+        let request = request.body(body.into()).map_err(ureq::Error::from)?;
+        Ok(self.agent.run(request)?)
+    }
+
+    fn send(&self, request: Builder) -> Result<ApiResponse> {
+        self.send_form(request, String::new())
+    }
+
+    fn send_form(&self, request: Builder, form_data: impl Into<Vec<u8>>) -> Result<ApiResponse> {
+        let mut response = self.execute(request, form_data)?;
+        Ok(serde_json::from_str(&response.body_mut().read_to_string()?)?)
+    }
+
+    /// 申请登录二维码
+    pub fn generate_qr_code(&self) -> Result<QrCodeData> {
+        let url = format!("{}/x/passport-login/web/qrcode/generate", self.base.passport);
+        self.send(self.get(&url))?.into_data()
+    }
+
+    /// 查询二维码扫描状态，登录成功时一并保留响应中的 Set-Cookie
+    pub fn poll_qr_code(&self, qrcode_key: &str) -> Result<QrPollData> {
+        let url = format!(
+            "{}/x/passport-login/web/qrcode/poll?qrcode_key={}",
+            self.base.passport, qrcode_key
+        );
+        let mut response = self.execute(self.get(&url), String::new())?;
+        let set_cookies = set_cookie_headers(response.headers());
+        let mut data: QrPollData =
+            serde_json::from_str::<ApiResponse>(&response.body_mut().read_to_string()?)?.into_data()?;
+        data.set_cookies = set_cookies;
+        Ok(data)
+    }
+
+    /// 根据扫码成功后的轮询结果生成登录凭据，并设置为当前凭据
+    ///
+    /// 跨域地址参数与 Set-Cookie 中的 Cookie 都会保存，后者优先。
+    pub fn login_with_poll(&mut self, poll: &QrPollData) -> Result<Cookies> {
+        let pairs = query_pairs(&poll.url);
+        let expires = pairs
+            .iter()
+            .find(|(name, _)| name == "Expires")
+            .and_then(|(_, value)| value.parse().ok())
+            .and_then(|seconds| chrono::DateTime::from_timestamp(seconds, 0));
+        let mut cookies = Cookies {
+            refresh_token: poll.refresh_token.clone(),
+            ..Default::default()
+        };
+        for (name, value) in pairs {
+            if !["Expires", "gourl", "first_domain"].contains(&name.as_str()) {
+                cookies.set(&name, value, expires);
+            }
+        }
+        for header in &poll.set_cookies {
+            cookies.apply_set_cookie(header);
+        }
+        self.cookies = Some(cookies);
+
+        // 设备标识获取失败不影响登录
+        if let Ok(spi) = self.finger_spi() {
+            let cookies = self.cookies.as_mut().ok_or(BiliLiveError::CookieError)?;
+            cookies.set("buvid3", spi.b_3, None);
+            cookies.set("buvid4", spi.b_4, None);
+        }
+
+        let mid = self.nav()?.mid;
+        let room_id = self.room_info(mid)?.roomid;
+
+        let cookies = self.cookies.as_mut().ok_or(BiliLiveError::CookieError)?;
+        cookies.room_id = room_id;
+        Ok(cookies.clone())
+    }
+
+    /// 退出登录，使当前凭据在服务端失效
+    pub fn logout(&self) -> Result<()> {
+        let url = format!("{}/login/exit/v2", self.base.passport);
+        let form_data = format!("biliCSRF={}", self.cookies()?.csrf_token());
+        self.send_form(self.authorized(self.post_form(&url))?, form_data)?
+            .into_data::<serde_json::Value>()?;
+        Ok(())
+    }
+
+    /// 查询当前凭据是否需要刷新
+    pub fn cookie_info(&self) -> Result<CookieInfoData> {
+        let url = format!(
+            "{}/x/passport-login/web/cookie/info?csrf={}",
+            self.base.passport,
+            self.cookies()?.csrf_token()
+        );
+        self.send(self.authorized(self.get(&url))?)?.into_data()
+    }
+
+    /// 使用 refresh_token 刷新登录凭据，新凭据设置为当前凭据
+    ///
+    /// 刷新成功后服务端已更换凭据，应先保存返回的新凭据，再调用 [`confirm_refresh`](Self::confirm_refresh)
+    /// 使旧的 refresh_token 失效。
+    pub fn refresh_cookies(&mut self) -> Result<Cookies> {
+        let old = self.cookies()?.clone();
+        if old.refresh_token.is_empty() {
+            return Err(BiliLiveError::RefreshError("本地凭据中没有 refresh_token".to_string()));
+        }
+
+        // 获取 refresh_csrf
+        let path = refresh::correspond_path(chrono::Utc::now().timestamp_millis())?;
+        let url = format!("{}/correspond/1/{}", self.base.www, path);
+        let html = self
+            .execute(self.get(&url).header(header::COOKIE, old.header_with_expired()), String::new())?
+            .body_mut()
+            .read_to_string()?;
+        let refresh_csrf = refresh::extract_refresh_csrf(&html)
+            .ok_or_else(|| BiliLiveError::RefreshError("页面中没有 refresh_csrf".to_string()))?;
+
+        // 刷新，新的 SESSDATA 与 bili_jct 只通过 Set-Cookie 返回
+        let url = format!("{}/x/passport-login/web/cookie/refresh", self.base.passport);
+        let form_data = format!(
+            "csrf={}&refresh_csrf={}&source=main_web&refresh_token={}",
+            old.csrf_token(), refresh_csrf, old.refresh_token
+        );
+        let request = self.post_form(&url).header(header::COOKIE, old.header_with_expired());
+        let mut response = self.execute(request, form_data)?;
+        let set_cookies = set_cookie_headers(response.headers());
+        let data: RefreshData =
+            serde_json::from_str::<ApiResponse>(&response.body_mut().read_to_string()?)?.into_data()?;
+        if !set_cookies.iter().any(|h| h.starts_with("SESSDATA=")) {
+            return Err(BiliLiveError::RefreshError("刷新接口未返回新的 SESSDATA".to_string()));
+        }
+        let mut cookies = old.clone();
+        for header in &set_cookies {
+            cookies.apply_set_cookie(header);
+        }
+        cookies.refresh_token = data.refresh_token;
+        self.cookies = Some(cookies.clone());
+        Ok(cookies)
+    }
+
+    /// 使用新凭据确认刷新，使刷新前的 refresh_token 失效
+    pub fn confirm_refresh(&self, old_refresh_token: &str) -> Result<()> {
+        let url = format!("{}/x/passport-login/web/confirm/refresh", self.base.passport);
+        let form_data = format!("csrf={}&refresh_token={}", self.cookies()?.csrf_token(), old_refresh_token);
+        self.send_form(self.authorized(self.post_form(&url))?, form_data)?
+            .into_data::<serde_json::Value>()?;
+        Ok(())
+    }
+
+    /// 获取设备标识 buvid3 与 buvid4
+    pub fn finger_spi(&self) -> Result<SpiData> {
+        let url = format!("{}/x/frontend/finger/spi", self.base.web);
+        self.send(self.get(&url))?.into_data()
+    }
+
+    /// 获取当前登录用户信息，未登录时返回接口错误
+    pub fn nav(&self) -> Result<NavData> {
+        let url = format!("{}/x/web-interface/nav", self.base.web);
+        self.send(self.authorized(self.get(&url))?)?.into_data()
+    }
+
+    /// 当前凭据是否处于登录状态
+    pub fn is_logged_in(&self) -> Result<bool> {
+        let url = format!("{}/x/web-interface/nav", self.base.web);
+        Ok(self.send(self.authorized(self.get(&url))?)?.code == 0)
+    }
+
+    /// 获取指定用户的直播间信息
+    pub fn room_info(&self, mid: i64) -> Result<RoomInfoData> {
+        let url = format!("{}/room/v1/Room/getRoomInfoOld?mid={}", self.base.live, mid);
+        self.send(self.get(&url))?.into_data()
+    }
+
+    /// 获取当前账号直播间的标题、简介与标签
+    pub fn room_detail(&self) -> Result<RoomDetailData> {
+        let url = format!("{}/room/v1/Room/get_info?room_id={}", self.base.live, self.cookies()?.room_id);
+        self.send(self.authorized(self.get(&url))?)?.into_data()
+    }
+
+    /// 获取当前账号直播间的公告
+    pub fn room_news(&self) -> Result<RoomNewsData> {
+        let url = format!("{}/room_ex/v1/RoomNews/get?roomid={}", self.base.live, self.cookies()?.room_id);
+        self.send(self.authorized(self.get(&url))?)?.into_data()
+    }
+
+    /// 获取全部直播分区
+    pub fn area_list(&self) -> Result<Vec<AreaParent>> {
+        let url = format!("{}/room/v1/Area/getList", self.base.live);
+        self.send(self.get(&url))?.into_data()
+    }
+
+    /// 携带上次的 ETag 获取分区列表，服务端返回 304 时表示列表没有变化
+    pub fn area_list_if_changed(&self, etag: Option<&str>) -> Result<AreaListFetch> {
+        let url = format!("{}/room/v1/Area/getList", self.base.live);
+        let mut request = self.get(&url);
+        if let Some(etag) = etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        let mut response = self.execute(request, String::new())?;
+        if response.status() == http::StatusCode::NOT_MODIFIED {
+            return Ok(AreaListFetch::NotModified);
+        }
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let areas = serde_json::from_str::<ApiResponse>(&response.body_mut().read_to_string()?)?.into_data()?;
+        Ok(AreaListFetch::Updated { areas, etag })
+    }
+
+    /// 获取直播间最近使用过的分区
+    pub fn recent_areas(&self) -> Result<Vec<RecentArea>> {
+        let url = format!(
+            "{}/room/v1/Area/getMyChooseArea?roomid={}",
+            self.base.live,
+            self.cookies()?.room_id
+        );
+        self.send(self.get(&url))?.into_data()
+    }
+
+    /// 开始直播，返回推流地址、推流码与本场直播的 live_key
+    pub fn start_live(&self, area_id: u32) -> Result<StartLiveData> {
+        let cookies = self.cookies()?;
+        let url = format!("{}/room/v1/Room/startLive", self.base.live);
+        let form_data = format!(
+            "room_id={}&area_v2={}&csrf={}&platform=pc_link",
+            cookies.room_id, area_id, cookies.csrf_token()
+        );
+        let request = self.post_form(&url).header("platform", "web_electron_link");
+        self.send_form(self.authorized(request)?, form_data)?.into_data()
+    }
+
+    /// 修改直播间标题
+    pub fn update_title(&self, title: &str) -> Result<()> {
+        self.update_room(&[("title", title)])
+    }
+
+    /// 修改直播间分区，直播中修改不会中断推流
+    pub fn update_area(&self, area_id: u32) -> Result<()> {
+        self.update_room(&[("area_id", &area_id.to_string())])
+    }
+
+    /// 修改直播间简介
+    pub fn update_description(&self, description: &str) -> Result<()> {
+        self.update_room(&[("description", description)])
+    }
+
+    /// 修改直播间标签，多个标签以逗号连接后提交
+    pub fn update_tags(&self, tags: &[String]) -> Result<()> {
+        self.update_room(&[("tags", &tags.join(","))])
+    }
+
+    /// 修改直播间公告，内容为空时清空公告
+    pub fn update_room_news(&self, content: &str) -> Result<()> {
+        let cookies = self.cookies()?;
+        let uid = match cookies.get("DedeUserID") {
+            "" => self.nav()?.mid.to_string(),
+            uid => uid.to_string(),
+        };
+        let url = format!("{}/xlive/app-blink/v1/index/updateRoomNews", self.base.live);
+        let form_data = format!(
+            "room_id={}&uid={}&content={}&csrf={3}&csrf_token={3}",
+            cookies.room_id,
+            uid,
+            form_encode(content),
+            cookies.csrf_token()
+        );
+        self.send_form(self.authorized(self.post_form(&url))?, form_data)?
+            .into_data::<serde_json::Value>()?;
+        Ok(())
+    }
+
+    /// 上传直播间封面图片，返回图片地址
+    pub fn upload_cover(&self, image: &[u8], content_type: &str) -> Result<String> {
+        let csrf = self.cookies()?.csrf_token();
+        let url = format!("{}/x/upload/web/image?csrf={}", self.base.web, csrf);
+        let boundary = format!("----BiliLiveBoundary{:x}", chrono::Utc::now().timestamp_millis());
+        let mut body = Vec::new();
+        for (name, value) in [("bucket", "live"), ("dir", "new_room_cover"), ("csrf", csrf)] {
+            body.extend_from_slice(
+                format!("--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n", boundary, name, value).as_bytes(),
+            );
+        }
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"cover\"\r\nContent-Type: {}\r\n\r\n",
+                boundary, content_type
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(image);
+        body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+        let request = http::Request::post(&url)
+            .header(header::USER_AGENT, &self.user_agent)
+            .header(header::CONTENT_TYPE, format!("multipart/form-data; boundary={}", boundary));
+        let data: UploadImageData = self.send_form(self.authorized(request)?, body)?.into_data()?;
+        Ok(data.location)
+    }
+
+    /// 将已上传的图片设为直播间封面
+    pub fn update_cover(&self, cover_url: &str) -> Result<()> {
+        let cookies = self.cookies()?;
+        let url = format!("{}/room/v1/Cover/new_replace_cover", self.base.live);
+        let form_data = format!(
+            "room_id={}&url={}&pic_id=0&type=cover&csrf={2}&csrf_token={2}",
+            cookies.room_id,
+            form_encode(cover_url),
+            cookies.csrf_token()
+        );
+        self.send_form(self.authorized(self.post_form(&url))?, form_data)?
+            .into_data::<serde_json::Value>()?;
+        Ok(())
+    }
+
+    /// 通过直播间信息更新接口修改指定字段
+    fn update_room(&self, fields: &[(&str, &str)]) -> Result<()> {
+        let cookies = self.cookies()?;
+        let url = format!("{}/room/v1/Room/update", self.base.live);
+        let mut form_data = format!("room_id={}", cookies.room_id);
+        for (key, value) in fields {
+            form_data.push_str(&format!("&{}={}", key, form_encode(value)));
+        }
+        form_data.push_str(&format!("&csrf={0}&csrf_token={0}", cookies.csrf_token()));
+        self.send_form(self.authorized(self.post_form(&url))?, form_data)?
+            .into_data::<serde_json::Value>()?;
+        Ok(())
+    }
+
+    /// 关闭直播
+    pub fn stop_live(&self) -> Result<()> {
+        let cookies = self.cookies()?;
+        let url = format!("{}/room/v1/Room/stopLive", self.base.live);
+        let form_data = format!(
+            "room_id={}&csrf={}&platform=web_electron_link",
+            cookies.room_id, cookies.csrf_token()
+        );
+        self.send_form(self.authorized(self.post_form(&url))?, form_data)?
+            .into_data::<serde_json::Value>()?;
+        Ok(())
+    }
+
+    /// 获取指定场次的直播统计信息
+    pub fn live_stats(&self, live_key: &str) -> Result<StopLiveData> {
+        let url = format!(
+            "{}/xlive/app-blink/v1/live/StopLiveData?live_key={}",
+            self.base.live, live_key
+        );
+        let request = self
+            .get(&url)
+            .header(header::CONTENT_TYPE, "application/json, text/plain, */*");
+        self.send(self.authorized(request)?)?.into_data()
+    }
+}
+
+fn agent(timeout: Duration) -> ureq::Agent {
+    ureq::Agent::config_builder().timeout_global(Some(timeout)).build().into()
+}
+
+/// 按 `application/x-www-form-urlencoded` 规则编码表单值
+fn form_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn query_pairs(url: &str) -> Vec<(String, String)> {
+    url.split('?')
+        .nth(1)
+        .unwrap_or("")
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+/// 响应中全部 Set-Cookie 响应头
+fn set_cookie_headers(headers: &http::HeaderMap) -> Vec<String> {
+    headers
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .map(str::to_string)
+        .collect()
+}
@@ -0,0 +1,809 @@
+use bili_live::{AreaParent, BiliLiveError, BilibiliClient, LiveArea, LiveSession, Result, Rtmp, StartLiveData};
+use clap::ValueEnum;
+use crate::config::Settings;
+use crate::credentials::CredentialStore;
+use crate::output::{self, AreaReport, OutputFormat, ProfileReport, RoomReport, StatusReport};
+use crate::paths::DataDirs;
+use crate::profile::{self, Profile, ProfileIndex, DEFAULT_PROFILE};
+use crate::{area_cache, cover, hooks, shutdown, tui, utils, StartArgs};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use crate::{user_info, user_prompt, user_success, user_warning};
+
+/// 检测到上次遗留的直播时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RecoverAction {
+    /// 继续等待上次的直播，下播时输出其统计
+    Resume,
+    /// 关闭上次的直播并输出统计，然后重新开播
+    Stop,
+    /// 重新获取推流码，将正在进行的直播作为本次直播
+    Adopt,
+}
+
+/// `room set` 要修改的直播间信息，为空的项保持不变
+#[derive(Debug, Default)]
+pub struct RoomUpdate {
+    pub title: Option<String>,
+    pub announcement: Option<String>,
+    pub intro: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+/// 各子命令共享的运行环境
+pub struct Context {
+    pub client: BilibiliClient,
+    pub output: OutputFormat,
+    pub no_prompt: bool,
+    pub credentials: CredentialStore,
+    /// 存放账号列表与各账号文件的目录
+    pub dirs: DataDirs,
+    pub profile: Profile,
+    /// 合并配置文件后实际生效的设置
+    pub settings: Settings,
+}
+
+/// 加载本地凭据，未登录时直接报错
+fn require_login(ctx: &mut Context) -> Result<()> {
+    if utils::check_status(&mut ctx.client, &ctx.credentials, &ctx.profile.cookies_file())? {
+        Ok(())
+    } else {
+        Err(BiliLiveError::LoginError("未登录或登录已失效，请先运行 bili_live login".to_string()))
+    }
+}
+
+/// 确定本次开播使用的分区
+fn choose_area(ctx: &Context, args: &StartArgs) -> Result<LiveArea> {
+    if let Some(id) = args.area {
+        user_success!("使用指定的分区: {}", id);
+        return Ok(LiveArea { id, name: None });
+    }
+
+    if !args.use_last_area && let Some(area) = &ctx.profile.settings.preferred_area {
+        user_success!("使用账号 {} 的偏好分区: {}", ctx.profile.name, area.id);
+        return Ok(area.clone());
+    }
+
+    if !args.use_last_area && let Some(id) = ctx.settings.area {
+        user_success!("使用配置文件中的默认分区: {}", id);
+        return Ok(LiveArea { id, name: None });
+    }
+
+    if args.use_last_area {
+        let area = utils::get_recent_live(&ctx.client)?;
+        user_success!("使用上次的分区: {} - {}", area.name.as_deref().unwrap_or_default(), area.id);
+        return Ok(area);
+    }
+    if ctx.no_prompt {
+        return Err(BiliLiveError::PromptRequired("未指定直播分区，请使用 --area 或 --use-last-area".to_string()));
+    }
+
+    let recent = utils::get_recent_areas(&ctx.client).unwrap_or_else(|e| {
+        user_warning!("获取最近使用的分区失败: {}", e);
+        Vec::new()
+    });
+    let favorites = &ctx.profile.settings.favorite_areas;
+    if args.tui {
+        return tui::pick_area(&area_list(ctx, false)?, &recent, favorites);
+    }
+    if let Some(area) = utils::choose_quick_pick(&recent, favorites)? {
+        return Ok(area);
+    }
+    user_info!("选择合适的直播分区！");
+    utils::get_area_choice(&area_list(ctx, false)?)
+}
+
+/// 为当前账号扫码登录，并登记到账号列表中
+fn login_profile(ctx: &mut Context) -> Result<()> {
+    ctx.profile.create_dirs()?;
+    utils::start_login(&mut ctx.client, &ctx.credentials, &ctx.profile.cookies_file())?;
+    user_success!("登录成功！");
+
+    let mut index = ProfileIndex::load(&ctx.dirs)?;
+    if !index.profiles.contains_key(&ctx.profile.name) {
+        index.profiles.insert(ctx.profile.name.clone(), ctx.profile.settings.clone());
+        index.save(&ctx.dirs)?;
+    }
+    Ok(())
+}
+
+/// 检查本地凭据，口令错误或缺少口令而无法解密时视为未登录
+fn check_stored_login(ctx: &mut Context) -> Result<bool> {
+    match utils::check_status(&mut ctx.client, &ctx.credentials, &ctx.profile.cookies_file()) {
+        Err(e @ (BiliLiveError::CryptoError(_) | BiliLiveError::PromptRequired(_))) => {
+            user_warning!("无法读取本地凭据: {}", e);
+            ctx.credentials.forget_secret();
+            Ok(false)
+        }
+        result => result,
+    }
+}
+
+/// 扫码登录，无法解密的旧凭据会被新凭据覆盖
+pub fn login(mut ctx: Context) -> Result<()> {
+    if check_stored_login(&mut ctx)? {
+        user_info!("当前已登录，将重新扫码登录");
+    }
+    login_profile(&mut ctx)
+}
+
+/// 退出登录并删除本地凭据，凭据无法解密时跳过服务端注销
+pub fn logout(mut ctx: Context) -> Result<()> {
+    let cookies_file = ctx.profile.cookies_file();
+    if !cookies_file.exists() {
+        user_info!("当前未登录");
+        return Ok(());
+    }
+    if check_stored_login(&mut ctx)? {
+        match ctx.client.logout() {
+            Ok(()) => user_success!("已在服务端注销登录"),
+            Err(e) => user_warning!("服务端注销失败: {}，仍将删除本地凭据", e),
+        }
+    }
+    std::fs::remove_file(cookies_file)?;
+    user_success!("已删除本地凭据");
+    Ok(())
+}
+
+/// 查看登录与直播间状态
+pub fn status(mut ctx: Context) -> Result<()> {
+    let mut report = StatusReport {
+        profile: ctx.profile.name.clone(),
+        ..Default::default()
+    };
+    if utils::check_status(&mut ctx.client, &ctx.credentials, &ctx.profile.cookies_file())? {
+        let nav = ctx.client.nav()?;
+        let room = ctx.client.room_info(nav.mid)?;
+        user_success!("已登录: {} (UID: {})", nav.uname, nav.mid);
+        if let Some(expires) = ctx.client.cookies()?.expires_at() {
+            user_info!("登录凭据有效期至: {}", expires.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"));
+        }
+        user_info!("直播间: {} - {}", room.roomid, room.title);
+        if room.is_live() {
+            user_success!("直播间正在直播");
+        } else {
+            user_info!("直播间未开播");
+        }
+        report = StatusReport {
+            profile: report.profile,
+            logged_in: true,
+            mid: Some(nav.mid),
+            uname: Some(nav.uname),
+            room_id: Some(room.roomid),
+            live: room.is_live(),
+            title: Some(room.title),
+        };
+    } else {
+        user_warning!("未登录");
+    }
+    ctx.output.emit_status(&report)
+}
+
+/// 登录（如有需要）并开播，直到收到退出信号后下播
+pub fn start(mut ctx: Context, args: &StartArgs) -> Result<()> {
+    if args.tui {
+        if ctx.output.is_machine_readable() || ctx.no_prompt {
+            return Err(BiliLiveError::InputError("--tui 不能与 --output json/env 或 --no-prompt 同时使用".to_string()));
+        }
+        tui::check_terminal()?;
+    }
+    if !utils::check_status(&mut ctx.client, &ctx.credentials, &ctx.profile.cookies_file())? {
+        if ctx.no_prompt {
+            return Err(BiliLiveError::PromptRequired("需要扫码登录，请先以交互模式运行一次完成登录".to_string()));
+        }
+        user_info!("需要登录，开始登录流程...");
+        login_profile(&mut ctx)?;
+    } else {
+        user_success!("登录状态正常");
+    }
+
+    let (session, rtmp) = match recover_session(&ctx, args)? {
+        Some(recovered) => recovered,
+        None => {
+            let area = choose_area(&ctx, args)?;
+            if let Some(title) = args.title.as_ref().or(ctx.settings.title.as_ref()) {
+                set_title(&ctx, title)?;
+            }
+
+            // 开始直播
+            user_info!("开始直播！");
+            let live = start_live(&ctx, args, area.id)?;
+            let room_id = ctx.client.cookies()?.room_id;
+            ctx.output.emit_started(room_id, &area, &live)?;
+            hooks::on_start(&ctx.settings.hooks, &output::started_vars(room_id, &area, &live));
+            (LiveSession::new(room_id, live.live_key, area), Some(live.rtmp))
+        }
+    };
+
+    // 记录本场直播，进程意外退出后仍可通过 stop 子命令下播
+    if let Err(e) = ctx.profile.create_dirs().and_then(|()| session.save(ctx.profile.session_file())) {
+        user_warning!("保存直播状态失败: {}", e);
+    }
+    let timeout = Duration::from_secs(ctx.settings.timeouts.shutdown);
+    wait_for_stop(ctx, session, args.tui, rtmp.as_ref(), timeout)
+}
+
+/// 开播并获取推流码，命令行未要求时按配置决定是否显示完整推流码
+fn start_live(ctx: &Context, args: &StartArgs, area_id: u32) -> Result<StartLiveData> {
+    let show_full_code = args.show_full_code_flag().unwrap_or(ctx.settings.show_full_code);
+    let copy_code = args.clipboard_flag().unwrap_or(ctx.settings.clipboard) && !ctx.output.is_machine_readable();
+    utils::start_live(&ctx.client, area_id, show_full_code, copy_code)
+}
+
+/// 等待退出信号，收到后在限定时间内关闭直播并输出统计
+///
+/// `tui` 为真时在全屏界面中等待，`rtmp` 为本次获取的推流地址，继续上次的直播时未知。
+fn wait_for_stop(ctx: Context, session: LiveSession, tui: bool, rtmp: Option<&Rtmp>, timeout: Duration) -> Result<()> {
+    let signals = shutdown::listen()?;
+    let stopped_in_tui = tui
+        && match tui::run_live(&ctx.client, &session, &ctx.profile.session_file(), rtmp, &signals) {
+            Ok(()) => true,
+            Err(e) => {
+                user_warning!("全屏界面异常退出: {}", e);
+                false
+            }
+        };
+    if stopped_in_tui {
+        user_info!("准备关闭直播！");
+    } else {
+        user_info!("请在本程序中按 Ctrl+C 关闭直播！否则直播将不会关闭！");
+
+        // 阻塞直到收到 Ctrl+C、SIGTERM、SIGHUP 或控制台关闭
+        let _ = signals.recv();
+        user_info!("收到退出信号，准备关闭直播！");
+    }
+    // 下播、获取统计与下播钩子共用同一个截止时间
+    let deadline = Instant::now() + timeout;
+
+    let Context { client, output, profile, settings, .. } = ctx;
+    // 直播期间可能已通过 switch-area 切换分区，以状态文件中的记录为准
+    let session = match LiveSession::load(profile.session_file()) {
+        Ok(Some(saved)) if saved.live_key == session.live_key => saved,
+        _ => session,
+    };
+    // 退出码只取决于下播请求，下播成功后统计获取失败或超时都只给出警告
+    let stop_client = client.clone();
+    shutdown::run_with_timeout(timeout, move || utils::stop_live(&stop_client))?;
+    user_success!("直播已关闭！");
+    if let Err(e) = LiveSession::remove(profile.session_file()) {
+        user_warning!("删除直播状态失败: {}", e);
+    }
+    let live_key = session.live_key.clone();
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    let stats = shutdown::run_with_timeout(remaining, move || Ok(utils::try_get_live_info(&client, &live_key)))
+        .unwrap_or_else(|_| {
+            user_warning!("获取直播统计超时，可稍后运行 bili_live stats {} 查询", session.live_key);
+            None
+        });
+    log_segments(&session);
+    let live_key = Some(session.live_key.as_str());
+    output.emit_stopped(session.room_id, live_key, stats.as_ref(), &session.segments)?;
+    let vars = output::stats_vars(session.room_id, live_key, stats.as_ref(), &session.segments);
+    hooks::on_stop(&settings.hooks, &vars, deadline.saturating_duration_since(Instant::now()));
+    Ok(())
+}
+
+/// 输出本场直播依次使用过的分区及各自的时长
+fn log_segments(session: &LiveSession) {
+    user_info!("分区记录:");
+    for (segment, duration) in session.segment_durations() {
+        let seconds = duration.num_seconds().max(0);
+        user_info!(
+            "{} 起 {} - {}，持续 {:02}:{:02}:{:02}",
+            segment.started_at.format("%Y-%m-%d %H:%M:%S"),
+            segment.area.name.as_deref().unwrap_or("未知分区"),
+            segment.area.id,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        );
+    }
+}
+
+/// 读取本地记录的直播场次，直播间与当前账号不一致时忽略
+fn load_session(ctx: &Context) -> Result<Option<LiveSession>> {
+    let room_id = ctx.client.cookies()?.room_id;
+    match LiveSession::load(ctx.profile.session_file()) {
+        Ok(Some(session)) if session.room_id != room_id => {
+            user_warning!("本地记录的直播间 {} 与当前账号的直播间 {} 不一致，已忽略", session.room_id, room_id);
+            Ok(None)
+        }
+        Ok(session) => Ok(session),
+        Err(e) => {
+            user_warning!("读取直播状态失败: {}", e);
+            Ok(None)
+        }
+    }
+}
+
+/// 询问如何处理上次遗留的直播
+fn prompt_recover_action(has_session: bool) -> Result<RecoverAction> {
+    loop {
+        if has_session {
+            user_prompt!("请选择: 1. 继续上次的直播 2. 关闭并输出统计后重新开播 3. 接管并重新获取推流码 (直接回车选择 1)");
+        } else {
+            user_prompt!("请选择: 2. 关闭后重新开播 3. 接管并重新获取推流码 (直接回车选择 3)");
+        }
+        match utils::read_line()?.as_str() {
+            "" if has_session => return Ok(RecoverAction::Resume),
+            "" => return Ok(RecoverAction::Adopt),
+            "1" if has_session => return Ok(RecoverAction::Resume),
+            "2" => return Ok(RecoverAction::Stop),
+            "3" => return Ok(RecoverAction::Adopt),
+            input => user_warning!("无效的选择: {}", input),
+        }
+    }
+}
+
+/// 检查上次运行遗留的直播，按选择继续、关闭或接管，返回需要继续等待下播的场次及重新获取的推流地址
+fn recover_session(ctx: &Context, args: &StartArgs) -> Result<Option<(LiveSession, Option<Rtmp>)>> {
+    let session = load_session(ctx)?;
+    let mid = ctx.client.nav()?.mid;
+    if !ctx.client.room_info(mid)?.is_live() {
+        // 直播已在别处结束，只补上本场统计
+        if let Some(session) = session {
+            user_warning!("上次的直播 {} 已结束", session.live_key);
+            if let Err(e) = utils::get_live_info(&ctx.client, &session.live_key) {
+                user_warning!("获取上次直播的统计信息失败: {}", e);
+            }
+            LiveSession::remove(ctx.profile.session_file())?;
+        }
+        return Ok(None);
+    }
+
+    match &session {
+        Some(session) => user_warning!(
+            "检测到上次未关闭的直播: {} (开播于 {})",
+            session.live_key,
+            session.started_at.format("%Y-%m-%d %H:%M:%S")
+        ),
+        None => user_warning!("直播间正在直播，但本地没有对应的直播记录"),
+    }
+
+    let action = match args.recover {
+        Some(action) => action,
+        None if ctx.no_prompt => {
+            return Err(BiliLiveError::PromptRequired("检测到未关闭的直播，请使用 --recover 指定处理方式".to_string()));
+        }
+        None => prompt_recover_action(session.is_some())?,
+    };
+    let room_id = ctx.client.cookies()?.room_id;
+
+    match (action, session) {
+        (RecoverAction::Resume, Some(session)) => {
+            user_success!("继续上次的直播");
+            ctx.output.emit_resumed(&session)?;
+            Ok(Some((session, None)))
+        }
+        (RecoverAction::Stop, session) => {
+            utils::stop_live(&ctx.client)?;
+            LiveSession::remove(ctx.profile.session_file())?;
+            let stats = session.as_ref().and_then(|s| utils::try_get_live_info(&ctx.client, &s.live_key));
+            if let Some(session) = &session {
+                log_segments(session);
+            }
+            let live_key = session.as_ref().map(|s| s.live_key.as_str());
+            let areas = session.as_ref().map(|s| s.segments.as_slice()).unwrap_or_default();
+            ctx.output.emit_stopped(room_id, live_key, stats.as_ref(), areas)?;
+            hooks::on_stop(
+                &ctx.settings.hooks,
+                &output::stats_vars(room_id, live_key, stats.as_ref(), areas),
+                Duration::from_secs(ctx.settings.timeouts.shutdown),
+            );
+            Ok(None)
+        }
+        (action, session) => {
+            if action == RecoverAction::Resume {
+                user_warning!("本地没有上次直播的记录，无法继续，将改为接管");
+            }
+            let area = match &session {
+                Some(session) => session.area.clone(),
+                None => utils::get_recent_live(&ctx.client)?,
+            };
+            user_info!("接管正在进行的直播，重新获取推流码");
+            let live = start_live(ctx, args, area.id)?;
+            ctx.output.emit_started(room_id, &area, &live)?;
+            hooks::on_start(&ctx.settings.hooks, &output::started_vars(room_id, &area, &live));
+            let mut adopted = LiveSession::new(room_id, live.live_key, area);
+            if let Some(session) = session {
+                adopted.started_at = session.started_at;
+                adopted.segments = session.segments;
+            }
+            Ok(Some((adopted, Some(live.rtmp))))
+        }
+    }
+}
+
+/// 关闭直播，提供 live_key 或存在本地记录的直播场次时同时输出本场统计
+pub fn stop(mut ctx: Context, live_key: Option<&str>) -> Result<()> {
+    require_login(&mut ctx)?;
+    let room_id = ctx.client.cookies()?.room_id;
+    let session = load_session(&ctx)?;
+    if let Some(session) = &session {
+        user_info!(
+            "找到本地记录的直播场次: {} (开播于 {})",
+            session.live_key,
+            session.started_at.format("%Y-%m-%d %H:%M:%S")
+        );
+    }
+
+    let live_key = live_key.or(session.as_ref().map(|s| s.live_key.as_str()));
+    utils::stop_live(&ctx.client)?;
+    if session.is_some() {
+        LiveSession::remove(ctx.profile.session_file())?;
+    }
+    let stats = live_key.and_then(|live_key| utils::try_get_live_info(&ctx.client, live_key));
+    let areas = session.as_ref().map(|s| s.segments.as_slice()).unwrap_or_default();
+    if let Some(session) = &session {
+        log_segments(session);
+    }
+    ctx.output.emit_stopped(room_id, live_key, stats.as_ref(), areas)?;
+    hooks::on_stop(
+        &ctx.settings.hooks,
+        &output::stats_vars(room_id, live_key, stats.as_ref(), areas),
+        Duration::from_secs(ctx.settings.timeouts.shutdown),
+    );
+    Ok(())
+}
+
+/// 直播中切换直播间分区，并在本地记录的直播场次中追加一个分区片段
+pub fn switch_area(mut ctx: Context, area: Option<u32>) -> Result<()> {
+    require_login(&mut ctx)?;
+    let mid = ctx.client.nav()?.mid;
+    if !ctx.client.room_info(mid)?.is_live() {
+        return Err(BiliLiveError::InputError("直播间未开播，开播时请使用 --area 指定分区".to_string()));
+    }
+
+    let area = match area {
+        Some(id) => {
+            let areas = area_list(&ctx, false)?;
+            let (_, area) = area_cache::find(&areas, id)
+                .ok_or_else(|| BiliLiveError::InputError(format!("分区 {} 不存在", id)))?;
+            LiveArea { id, name: Some(area.name.clone()) }
+        }
+        None if ctx.no_prompt => {
+            return Err(BiliLiveError::PromptRequired("未指定要切换到的分区，请使用 --area".to_string()));
+        }
+        None => {
+            user_info!("选择要切换到的直播分区！");
+            utils::get_area_choice(&area_list(&ctx, false)?)?
+        }
+    };
+    ctx.client.update_area(area.id)?;
+    user_success!("直播分区已切换为: {} - {}", area.name.as_deref().unwrap_or_default(), area.id);
+
+    let room_id = ctx.client.cookies()?.room_id;
+    match load_session(&ctx)? {
+        Some(mut session) => {
+            session.switch_area(area.clone());
+            if let Err(e) = session.save(ctx.profile.session_file()) {
+                user_warning!("保存直播状态失败: {}", e);
+            }
+        }
+        None => user_warning!("本地没有正在进行的直播记录，本次切换不会出现在下播统计中"),
+    }
+    ctx.output.emit_area_switched(room_id, &area)
+}
+
+/// 修改直播间标题
+fn set_title(ctx: &Context, title: &str) -> Result<()> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err(BiliLiveError::InputError("直播间标题不能为空".to_string()));
+    }
+    ctx.client.update_title(title)?;
+    user_success!("直播间标题已修改为: {}", title);
+    Ok(())
+}
+
+/// 查看或修改直播间标题
+pub fn title(mut ctx: Context, title: Option<&str>) -> Result<()> {
+    require_login(&mut ctx)?;
+    let room_id = ctx.client.cookies()?.room_id;
+    let title = match title {
+        Some(title) => {
+            set_title(&ctx, title)?;
+            title.trim().to_string()
+        }
+        None => {
+            let mid = ctx.client.nav()?.mid;
+            let title = ctx.client.room_info(mid)?.title;
+            user_info!("直播间标题: {}", title);
+            title
+        }
+    };
+    ctx.output.emit_title(room_id, &title)
+}
+
+/// 显示直播间的标题、公告、简介与标签
+pub fn room_show(mut ctx: Context) -> Result<()> {
+    require_login(&mut ctx)?;
+    let detail = ctx.client.room_detail()?;
+    let report = RoomReport {
+        room_id: detail.room_id,
+        announcement: ctx.client.room_news()?.content,
+        tags: detail.tag_list(),
+        title: detail.title,
+        description: detail.description,
+    };
+    if !ctx.output.is_machine_readable() {
+        user_info!("直播间: {}", report.room_id);
+        user_info!("标题: {}", report.title);
+        user_info!("公告: {}", report.announcement);
+        user_info!("简介: {}", report.description);
+        user_info!("标签: {}", report.tags.join(", "));
+    }
+    ctx.output.emit_room(&report)
+}
+
+/// 依次修改指定的直播间信息，任一项失败即停止
+pub fn room_set(mut ctx: Context, update: RoomUpdate) -> Result<()> {
+    let RoomUpdate { title, announcement, intro, tags } = update;
+    if title.is_none() && announcement.is_none() && intro.is_none() && tags.is_none() {
+        return Err(BiliLiveError::InputError(
+            "请至少指定 --title、--announcement、--intro、--tags 中的一项".to_string(),
+        ));
+    }
+    require_login(&mut ctx)?;
+    if let Some(title) = title {
+        set_title(&ctx, &title)?;
+    }
+    if let Some(announcement) = announcement {
+        ctx.client.update_room_news(announcement.trim())?;
+        user_success!("直播间公告已更新");
+    }
+    if let Some(intro) = intro {
+        ctx.client.update_description(intro.trim())?;
+        user_success!("直播间简介已更新");
+    }
+    if let Some(tags) = tags {
+        let tags: Vec<String> = tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()).map(str::to_string).collect();
+        ctx.client.update_tags(&tags)?;
+        user_success!("直播间标签已更新: {}", tags.join(", "));
+    }
+    Ok(())
+}
+
+/// 处理并上传直播间封面，`dry_run` 指定时只将处理后的图片保存到该位置
+pub fn cover_set(mut ctx: Context, path: &Path, dry_run: Option<PathBuf>) -> Result<()> {
+    let image = cover::prepare(path)?;
+    user_success!("已将 {} 处理为 {}x{} 的封面", path.display(), cover::COVER_WIDTH, cover::COVER_HEIGHT);
+    if let Some(out) = dry_run {
+        std::fs::write(&out, &image)?;
+        user_success!("试运行：处理后的封面已保存到 {}，未上传", out.display());
+        return Ok(());
+    }
+
+    require_login(&mut ctx)?;
+    let url = ctx.client.upload_cover(&image, cover::CONTENT_TYPE)?;
+    user_info!("封面已上传: {}", url);
+    ctx.client.update_cover(&url)?;
+    user_success!("直播间封面已更新");
+    let room_id = ctx.client.cookies()?.room_id;
+    ctx.output.emit_cover(room_id, &url)
+}
+
+/// 读取分区列表，优先使用状态目录中未过期的缓存
+fn area_list(ctx: &Context, refresh: bool) -> Result<Vec<AreaParent>> {
+    let path = ctx.dirs.state.join(area_cache::AREA_CACHE_FILE);
+    area_cache::load(&ctx.client, &path, ctx.settings.cache.area_ttl, refresh)
+}
+
+/// 以文本或机器可读格式输出分区树
+fn print_areas(ctx: &Context, areas: &[AreaParent]) -> Result<()> {
+    if ctx.output.is_machine_readable() {
+        return ctx.output.emit_areas(areas);
+    }
+    for parent in areas {
+        user_info!("{}:", parent.name);
+        for area in &parent.list {
+            user_info!("    {} - {}", area.id, area.name);
+        }
+    }
+    Ok(())
+}
+
+/// 列出全部直播分区
+pub fn areas_list(ctx: Context, refresh: bool) -> Result<()> {
+    let areas = area_list(&ctx, refresh)?;
+    print_areas(&ctx, &areas)
+}
+
+/// 按关键字搜索分区名称或ID
+pub fn areas_search(ctx: Context, keyword: &str, refresh: bool) -> Result<()> {
+    let found = area_cache::search(&area_list(&ctx, refresh)?, keyword);
+    if found.is_empty() {
+        user_warning!("没有找到与 {} 匹配的分区", keyword);
+    }
+    print_areas(&ctx, &found)
+}
+
+/// 显示指定ID的分区
+pub fn areas_show(ctx: Context, id: u32, refresh: bool) -> Result<()> {
+    let areas = area_list(&ctx, refresh)?;
+    let (parent, area) = area_cache::find(&areas, id)
+        .ok_or_else(|| BiliLiveError::InputError(format!("分区 {} 不存在", id)))?;
+    if !ctx.output.is_machine_readable() {
+        user_info!("{} - {} ({})", area.id, area.name, parent.name);
+    }
+    ctx.output.emit_area(&AreaReport { id: area.id, name: area.name.clone(), parent_name: parent.name.clone() })
+}
+
+/// 读取账号列表，当前账号不存在时报错，避免把写错的账号名登记为新账号；默认账号总是存在
+fn load_index_with_profile(ctx: &Context) -> Result<ProfileIndex> {
+    let index = ProfileIndex::load(&ctx.dirs)?;
+    if ctx.profile.name != DEFAULT_PROFILE && !index.names(&ctx.dirs).contains(&ctx.profile.name) {
+        return Err(BiliLiveError::InputError(format!("账号 {} 不存在", ctx.profile.name)));
+    }
+    Ok(index)
+}
+
+/// 列出当前账号收藏的分区
+pub fn favorite_list(ctx: Context) -> Result<()> {
+    let favorites = &ctx.profile.settings.favorite_areas;
+    if !ctx.output.is_machine_readable() {
+        if favorites.is_empty() {
+            user_info!("账号 {} 还没有收藏分区", ctx.profile.name);
+        }
+        for area in favorites {
+            user_info!("{} - {}", area.id, area.name.as_deref().unwrap_or("未知分区"));
+        }
+    }
+    ctx.output.emit_favorites(favorites)
+}
+
+/// 收藏分区，分区名称从分区列表中查找
+pub fn favorite_add(ctx: Context, id: u32, refresh: bool) -> Result<()> {
+    let areas = area_list(&ctx, refresh)?;
+    let (_, area) = area_cache::find(&areas, id)
+        .ok_or_else(|| BiliLiveError::InputError(format!("分区 {} 不存在", id)))?;
+    let mut index = load_index_with_profile(&ctx)?;
+    let favorites = &mut index.profiles.entry(ctx.profile.name.clone()).or_default().favorite_areas;
+    if favorites.iter().any(|a| a.id == id) {
+        user_info!("分区 {} - {} 已在收藏中", area.id, area.name);
+        return Ok(());
+    }
+    favorites.push(LiveArea { id, name: Some(area.name.clone()) });
+    index.save(&ctx.dirs)?;
+    user_success!("已收藏分区 {} - {}", area.id, area.name);
+    Ok(())
+}
+
+/// 取消收藏分区
+pub fn favorite_remove(ctx: Context, id: u32) -> Result<()> {
+    let mut index = load_index_with_profile(&ctx)?;
+    let favorites = &mut index.profiles.entry(ctx.profile.name.clone()).or_default().favorite_areas;
+    let Some(i) = favorites.iter().position(|a| a.id == id) else {
+        return Err(BiliLiveError::InputError(format!("分区 {} 不在收藏中", id)));
+    };
+    favorites.remove(i);
+    index.save(&ctx.dirs)?;
+    user_success!("已取消收藏分区 {}", id);
+    Ok(())
+}
+
+/// 查询指定场次的直播统计
+pub fn stats(mut ctx: Context, live_key: &str) -> Result<()> {
+    require_login(&mut ctx)?;
+    let room_id = ctx.client.cookies()?.room_id;
+    let stats = utils::get_live_info(&ctx.client, live_key)?;
+    ctx.output.emit_stats(room_id, live_key, &stats)
+}
+
+/// 列出全部账号
+pub fn profile_list(ctx: Context) -> Result<()> {
+    let index = ProfileIndex::load(&ctx.dirs)?;
+    let mut reports = Vec::new();
+    for name in index.names(&ctx.dirs) {
+        let profile = index.profile(&ctx.dirs, Some(&name))?;
+        reports.push(ProfileReport {
+            default: name == index.default_name(),
+            logged_in: profile.cookies_file().exists(),
+            preferred_area: profile.settings.preferred_area,
+            name,
+        });
+    }
+    if ctx.output.is_machine_readable() {
+        return ctx.output.emit_profiles(&reports);
+    }
+    if reports.is_empty() {
+        user_info!("还没有任何账号，请运行 bili_live profile add <账号名> 添加");
+    }
+    for report in &reports {
+        user_info!(
+            "{} {}{}{}",
+            if report.default { "*" } else { " " },
+            report.name,
+            report.preferred_area.as_ref().map(|a| format!(" (偏好分区: {})", a.id)).unwrap_or_default(),
+            if report.logged_in { "" } else { " (未登录)" },
+        );
+    }
+    Ok(())
+}
+
+/// 扫码登录并添加账号
+pub fn profile_add(mut ctx: Context, name: &str, area: Option<u32>) -> Result<()> {
+    if ctx.no_prompt {
+        return Err(BiliLiveError::PromptRequired("添加账号需要扫码登录".to_string()));
+    }
+    let mut index = ProfileIndex::load(&ctx.dirs)?;
+    ctx.profile = index.profile(&ctx.dirs, Some(name))?;
+    if let Some(id) = area {
+        ctx.profile.settings.preferred_area = Some(LiveArea { id, name: None });
+    }
+    login_profile(&mut ctx)?;
+
+    index = ProfileIndex::load(&ctx.dirs)?;
+    index.profiles.insert(ctx.profile.name.clone(), ctx.profile.settings.clone());
+    index.save(&ctx.dirs)?;
+    user_success!("已添加账号 {}", ctx.profile.name);
+    Ok(())
+}
+
+/// 删除账号的本地凭据
+pub fn profile_remove(ctx: Context, name: &str) -> Result<()> {
+    let mut index = ProfileIndex::load(&ctx.dirs)?;
+    if !index.names(&ctx.dirs).iter().any(|n| n == name) {
+        return Err(BiliLiveError::InputError(format!("账号 {} 不存在", name)));
+    }
+    let profile = index.profile(&ctx.dirs, Some(name))?;
+    if profile.session_file().exists() {
+        return Err(BiliLiveError::InputError(format!(
+            "账号 {} 仍有未关闭的直播，请先运行 bili_live --profile {} stop",
+            name, name
+        )));
+    }
+    profile.remove_files()?;
+    index.profiles.remove(name);
+    if index.default.as_deref() == Some(name) {
+        index.default = None;
+    }
+    index.save(&ctx.dirs)?;
+    user_success!("已删除账号 {}", name);
+    Ok(())
+}
+
+/// 设置默认账号
+pub fn profile_default(ctx: Context, name: &str) -> Result<()> {
+    profile::validate_name(name)?;
+    let mut index = ProfileIndex::load(&ctx.dirs)?;
+    if !index.names(&ctx.dirs).iter().any(|n| n == name) {
+        return Err(BiliLiveError::InputError(format!("账号 {} 不存在", name)));
+    }
+    index.default = (name != DEFAULT_PROFILE).then(|| name.to_string());
+    index.save(&ctx.dirs)?;
+    user_success!("默认账号已设置为 {}", name);
+    Ok(())
+}
+
+/// 设置或清除账号的偏好分区
+pub fn profile_set_area(ctx: Context, name: &str, area: Option<u32>) -> Result<()> {
+    profile::validate_name(name)?;
+    let mut index = ProfileIndex::load(&ctx.dirs)?;
+    if !index.names(&ctx.dirs).iter().any(|n| n == name) {
+        return Err(BiliLiveError::InputError(format!("账号 {} 不存在", name)));
+    }
+    index.profiles.entry(name.to_string()).or_default().preferred_area = area.map(|id| LiveArea { id, name: None });
+    index.save(&ctx.dirs)?;
+    match area {
+        Some(id) => user_success!("账号 {} 的偏好分区已设置为 {}", name, id),
+        None => user_success!("已清除账号 {} 的偏好分区", name),
+    }
+    Ok(())
+}
+
+/// 显示实际生效的设置
+pub fn config(ctx: Context, config_file: &Path) -> Result<()> {
+    let note = if config_file.exists() {
+        format!("配置文件: {}", config_file.display())
+    } else {
+        format!("配置文件 {} 不存在，使用默认设置", config_file.display())
+    };
+    // 文本输出本身就是 TOML，说明写成注释，使输出可以直接保存为配置文件
+    if ctx.output.is_machine_readable() {
+        user_info!("{}", note);
+    } else {
+        println!("# {}", note);
+    }
+    ctx.output.emit_settings(&ctx.settings)
+}
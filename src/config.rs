@@ -0,0 +1,114 @@
+use bili_live::Result;
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use crate::output::OutputFormat;
+
+pub const CONFIG_FILE: &str = "bili_live.toml";
+
+/// 配置文件内容，所有字段均可省略
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// 未指定分区且账号没有偏好分区时使用的分区ID
+    pub area: Option<u32>,
+    /// 开播时使用的直播间标题
+    pub title: Option<String>,
+    pub show_full_code: Option<bool>,
+    /// 开播后是否将推流码复制到剪贴板
+    pub clipboard: Option<bool>,
+    pub output: Option<OutputFormat>,
+    pub log_level: Option<LevelFilter>,
+    pub api: ApiConfig,
+    pub timeouts: TimeoutConfig,
+    pub cache: CacheConfig,
+    pub hooks: HookConfig,
+}
+
+/// `[api]` 段：覆盖各类接口的服务地址
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ApiConfig {
+    pub passport_url: Option<String>,
+    pub web_url: Option<String>,
+    pub live_url: Option<String>,
+    pub www_url: Option<String>,
+}
+
+/// `[timeouts]` 段：各类超时时间，单位为秒
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TimeoutConfig {
+    pub http: Option<u64>,
+    pub shutdown: Option<u64>,
+}
+
+/// `[cache]` 段：本地缓存的有效期，单位为秒
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub area_ttl: Option<u64>,
+}
+
+/// `[hooks]` 段：开播与下播后执行的命令
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HookConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_start: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_stop: Option<String>,
+}
+
+/// 合并命令行、环境变量、配置文件与默认值后实际生效的设置
+#[derive(Debug, Clone, Serialize)]
+pub struct Settings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub area: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub show_full_code: bool,
+    pub clipboard: bool,
+    pub output: OutputFormat,
+    #[serde(serialize_with = "serialize_level")]
+    pub log_level: LevelFilter,
+    pub api: ApiSettings,
+    pub timeouts: TimeoutSettings,
+    pub cache: CacheSettings,
+    pub hooks: HookConfig,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiSettings {
+    pub passport_url: String,
+    pub web_url: String,
+    pub live_url: String,
+    pub www_url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeoutSettings {
+    pub http: u64,
+    pub shutdown: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheSettings {
+    pub area_ttl: u64,
+}
+
+/// 与配置文件的写法一致，使用小写的日志级别
+fn serialize_level<S: serde::Serializer>(level: &LevelFilter, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&level.as_str().to_lowercase())
+}
+
+impl Config {
+    /// 读取配置文件，文件不存在时使用默认配置
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
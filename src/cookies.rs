@@ -0,0 +1,206 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use crate::crypto::{self, Sealed};
+use crate::error::{BiliLiveError, Result};
+
+pub const COOKIES_FILE: &str = "cookies.json";
+
+/// 未登录的请求也会携带的设备标识
+const DEVICE_COOKIES: [&str; 3] = ["buvid3", "buvid4", "b_nut"];
+
+/// 单个 Cookie 的值与过期时间，过期时间未知时为空
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CookieValue {
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<DateTime<Utc>>,
+}
+
+impl CookieValue {
+    pub fn is_expired(&self) -> bool {
+        self.expires.is_some_and(|expires| expires <= Utc::now())
+    }
+}
+
+/// 登录凭据：直播间号、refresh_token 与登录时获得的全部 Cookie
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "CookiesFile")]
+pub struct Cookies {
+    pub room_id: i64,
+    pub refresh_token: String,
+    pub cookies: BTreeMap<String, CookieValue>,
+}
+
+/// 兼容旧版本只保存了 `sessdata` 与 `csrf_token` 的凭据文件
+#[derive(Deserialize)]
+struct CookiesFile {
+    room_id: i64,
+    #[serde(default)]
+    refresh_token: String,
+    #[serde(default)]
+    cookies: BTreeMap<String, CookieValue>,
+    sessdata: Option<String>,
+    csrf_token: Option<String>,
+}
+
+impl From<CookiesFile> for Cookies {
+    fn from(file: CookiesFile) -> Self {
+        let mut cookies = Cookies {
+            room_id: file.room_id,
+            refresh_token: file.refresh_token,
+            cookies: file.cookies,
+        };
+        for (name, value) in [("SESSDATA", file.sessdata), ("bili_jct", file.csrf_token)] {
+            if let Some(value) = value.filter(|_| !cookies.cookies.contains_key(name)) {
+                cookies.set(name, value, None);
+            }
+        }
+        cookies
+    }
+}
+
+impl Cookies {
+    /// 从明文文件读取登录凭据
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let cookies_str = fs::read_to_string(path)?;
+        if Sealed::detect(&cookies_str).is_some() {
+            return Err(BiliLiveError::CryptoError("凭据文件已加密，需要提供口令".to_string()));
+        }
+        Ok(serde_json::from_str(&cookies_str)?)
+    }
+
+    /// 将登录凭据以明文写入文件，文件仅对当前用户可读写
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let cookies_json = serde_json::to_string_pretty(self)?;
+        write_private(path.as_ref(), cookies_json.as_bytes())
+    }
+
+    /// 读取凭据文件，文件已加密时用 `secret` 解密，明文文件直接读取
+    pub fn load_encrypted(path: impl AsRef<Path>, secret: &[u8]) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        match Sealed::detect(&content) {
+            Some(sealed) => Ok(serde_json::from_slice(&crypto::open(&sealed, secret)?)?),
+            None => Ok(serde_json::from_str(&content)?),
+        }
+    }
+
+    /// 用 `secret` 派生的密钥加密后写入文件，文件仅对当前用户可读写
+    pub fn save_encrypted(&self, path: impl AsRef<Path>, secret: &[u8]) -> Result<()> {
+        let sealed = crypto::seal(&serde_json::to_vec(self)?, secret)?;
+        write_private(path.as_ref(), serde_json::to_string_pretty(&sealed)?.as_bytes())
+    }
+
+    /// 凭据文件是否已加密
+    pub fn is_encrypted(path: impl AsRef<Path>) -> Result<bool> {
+        Ok(Sealed::detect(&fs::read_to_string(path)?).is_some())
+    }
+
+    /// 指定 Cookie 的值，不存在时为空字符串
+    pub fn get(&self, name: &str) -> &str {
+        self.cookies.get(name).map(|c| c.value.as_str()).unwrap_or_default()
+    }
+
+    pub fn set(&mut self, name: &str, value: impl Into<String>, expires: Option<DateTime<Utc>>) {
+        self.cookies.insert(name.to_string(), CookieValue { value: value.into(), expires });
+    }
+
+    pub fn sessdata(&self) -> &str {
+        self.get("SESSDATA")
+    }
+
+    /// bili_jct，即各类写操作所需的 csrf
+    pub fn csrf_token(&self) -> &str {
+        self.get("bili_jct")
+    }
+
+    /// SESSDATA 的过期时间
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.cookies.get("SESSDATA")?.expires
+    }
+
+    /// 合并一条 Set-Cookie 响应头，已过期的 Cookie 会被删除
+    pub fn apply_set_cookie(&mut self, header: &str) {
+        let Some((name, cookie)) = parse_set_cookie(header) else {
+            return;
+        };
+        if cookie.is_expired() {
+            self.cookies.remove(&name);
+        } else {
+            self.cookies.insert(name, cookie);
+        }
+    }
+
+    /// 登录后请求携带的 Cookie 请求头，跳过已过期的 Cookie
+    pub fn header(&self) -> String {
+        join_header(self.cookies.iter().filter(|(_, c)| !c.is_expired()))
+    }
+
+    /// 包含已过期 Cookie 的请求头，刷新登录凭据时使用
+    pub fn header_with_expired(&self) -> String {
+        join_header(self.cookies.iter())
+    }
+
+    /// 未登录的请求携带的 Cookie 请求头，只包含设备标识
+    pub fn device_header(&self) -> String {
+        join_header(
+            self.cookies
+                .iter()
+                .filter(|(name, c)| DEVICE_COOKIES.contains(&name.as_str()) && !c.is_expired()),
+        )
+    }
+}
+
+/// 以 0600 权限写入文件，已存在的文件也会收紧权限
+fn write_private(path: &Path, content: &[u8]) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        if path.exists() {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    options.open(path)?.write_all(content)?;
+    Ok(())
+}
+
+fn join_header<'a>(cookies: impl Iterator<Item = (&'a String, &'a CookieValue)>) -> String {
+    cookies
+        .map(|(name, cookie)| format!("{}={}", name, cookie.value))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// 解析 Set-Cookie 响应头，`Max-Age` 优先于 `Expires`
+pub fn parse_set_cookie(header: &str) -> Option<(String, CookieValue)> {
+    let mut parts = header.split(';');
+    let (name, value) = parts.next()?.split_once('=')?;
+    let mut expires = None;
+    let mut max_age = None;
+    for attribute in parts {
+        let Some((key, val)) = attribute.split_once('=') else {
+            continue;
+        };
+        match key.trim().to_ascii_lowercase().as_str() {
+            "max-age" => max_age = val.trim().parse::<i64>().ok(),
+            "expires" => {
+                // 部分服务端使用 `18-Apr-2026` 形式的日期
+                expires = DateTime::parse_from_rfc2822(val.trim())
+                    .or_else(|_| DateTime::parse_from_rfc2822(&val.trim().replace('-', " ")))
+                    .ok()
+                    .map(|t| t.with_timezone(&Utc))
+            }
+            _ => {}
+        }
+    }
+    if let Some(seconds) = max_age {
+        expires = Some(Utc::now() + chrono::Duration::seconds(seconds));
+    }
+    Some((name.trim().to_string(), CookieValue { value: value.trim().to_string(), expires }))
+}
@@ -0,0 +1,48 @@
+use bili_live::{BiliLiveError, Result};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{GenericImageView, ImageReader};
+use std::path::{Path, PathBuf};
+
+/// 直播间封面要求 16:9，统一输出为 1280x720
+pub const COVER_WIDTH: u32 = 1280;
+pub const COVER_HEIGHT: u32 = 720;
+
+/// 裁剪后低于该尺寸的图片放大后会明显模糊，直接拒绝
+const MIN_WIDTH: u32 = 640;
+const MIN_HEIGHT: u32 = 360;
+
+const JPEG_QUALITY: u8 = 90;
+
+pub const CONTENT_TYPE: &str = "image/jpeg";
+
+/// 读取图片，居中裁剪为 16:9 后缩放到封面尺寸，并编码为 JPEG
+pub fn prepare(path: &Path) -> Result<Vec<u8>> {
+    let image = ImageReader::open(path)?.with_guessed_format()?.decode()?;
+    let (width, height) = image.dimensions();
+    let (crop_width, crop_height) = if u64::from(width) * 9 > u64::from(height) * 16 {
+        ((u64::from(height) * 16 / 9) as u32, height)
+    } else {
+        (width, (u64::from(width) * 9 / 16) as u32)
+    };
+    if crop_width < MIN_WIDTH || crop_height < MIN_HEIGHT {
+        return Err(BiliLiveError::InputError(format!(
+            "图片尺寸 {}x{} 过小，裁剪为 16:9 后至少需要 {}x{}",
+            width, height, MIN_WIDTH, MIN_HEIGHT
+        )));
+    }
+
+    let cover = image
+        .crop_imm((width - crop_width) / 2, (height - crop_height) / 2, crop_width, crop_height)
+        .resize_exact(COVER_WIDTH, COVER_HEIGHT, FilterType::Lanczos3)
+        .to_rgb8();
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY).encode_image(&cover)?;
+    Ok(jpeg)
+}
+
+/// 试运行时处理后图片的默认保存位置：与原图同目录的 `<原文件名>_cover.jpg`
+pub fn dry_run_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    path.with_file_name(format!("{}_cover.jpg", stem))
+}
@@ -0,0 +1,105 @@
+use bili_live::{BiliLiveError, Cookies, Result};
+use std::cell::OnceCell;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use crate::utils;
+use crate::{user_input_prompt, user_success, user_warning};
+
+/// 提供凭据口令的环境变量
+pub const PASSPHRASE_ENV: &str = "BILI_LIVE_PASSPHRASE";
+
+/// 凭据文件的读写方式：默认加密保存，口令依次取自密钥文件、环境变量与交互输入
+pub struct CredentialStore {
+    key_file: Option<PathBuf>,
+    plaintext: bool,
+    no_prompt: bool,
+    secret: OnceCell<Vec<u8>>,
+}
+
+impl CredentialStore {
+    pub fn new(key_file: Option<PathBuf>, plaintext: bool, no_prompt: bool) -> Self {
+        Self { key_file, plaintext, no_prompt, secret: OnceCell::new() }
+    }
+
+    /// 读取凭据，未指定 `--insecure-plaintext` 时会顺带加密旧版本的明文凭据
+    pub fn load(&self, path: &Path) -> Result<Cookies> {
+        if Cookies::is_encrypted(path)? {
+            return Cookies::load_encrypted(path, self.secret(false)?);
+        }
+        let cookies = Cookies::load(path)?;
+        if !self.plaintext {
+            match self.secret(true) {
+                Ok(secret) => {
+                    cookies.save_encrypted(path, secret)?;
+                    user_success!("已将明文保存的登录凭据加密");
+                }
+                Err(e) => user_warning!("登录凭据仍以明文保存: {}，如确需明文保存请使用 --insecure-plaintext", e),
+            }
+        }
+        Ok(cookies)
+    }
+
+    pub fn save(&self, cookies: &Cookies, path: &Path) -> Result<()> {
+        if self.plaintext {
+            cookies.save(path)
+        } else {
+            cookies.save_encrypted(path, self.secret(true)?)
+        }
+    }
+
+    /// 丢弃已缓存的口令，下次加解密时重新获取
+    pub fn forget_secret(&mut self) {
+        self.secret = OnceCell::new();
+    }
+
+    /// 取得口令并在本次运行中缓存，`confirm` 为真时交互输入需要输入两次
+    fn secret(&self, confirm: bool) -> Result<&[u8]> {
+        if let Some(secret) = self.secret.get() {
+            return Ok(secret);
+        }
+        let secret = self.read_secret(confirm)?;
+        Ok(self.secret.get_or_init(|| secret))
+    }
+
+    fn read_secret(&self, confirm: bool) -> Result<Vec<u8>> {
+        if let Some(path) = &self.key_file {
+            let key = fs::read(path)
+                .map_err(|e| BiliLiveError::CryptoError(format!("读取密钥文件 {} 失败: {}", path.display(), e)))?;
+            if key.is_empty() {
+                return Err(BiliLiveError::CryptoError(format!("密钥文件 {} 为空", path.display())));
+            }
+            return Ok(key);
+        }
+        if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV)
+            && !passphrase.is_empty()
+        {
+            return Ok(passphrase.into_bytes());
+        }
+        if self.no_prompt {
+            return Err(BiliLiveError::PromptRequired(format!(
+                "需要口令加解密登录凭据，请设置 {} 或使用 --key-file",
+                PASSPHRASE_ENV
+            )));
+        }
+
+        let passphrase = read_passphrase("请输入登录凭据的口令: ")?;
+        if passphrase.is_empty() {
+            return Err(BiliLiveError::InputError("口令不能为空".to_string()));
+        }
+        if confirm && read_passphrase("请再次输入口令: ")? != passphrase {
+            return Err(BiliLiveError::InputError("两次输入的口令不一致".to_string()));
+        }
+        Ok(passphrase.into_bytes())
+    }
+}
+
+/// 在终端中输入时不回显，标准输入不是终端时按行读取
+fn read_passphrase(prompt: &str) -> Result<String> {
+    user_input_prompt!("{}", prompt);
+    if std::io::stdin().is_terminal() {
+        rpassword::read_password().map_err(|e| BiliLiveError::InputError(format!("读取口令失败: {}", e)))
+    } else {
+        utils::read_line()
+    }
+}
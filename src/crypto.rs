@@ -0,0 +1,83 @@
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, rand_core::RngCore};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use crate::error::{BiliLiveError, Result};
+
+const VERSION: u32 = 1;
+const KDF: &str = "argon2id";
+const CIPHER: &str = "xchacha20poly1305";
+const SALT_LEN: usize = 16;
+
+/// 加密后的凭据文件内容，各字段均为小写十六进制
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Sealed {
+    version: u32,
+    kdf: String,
+    cipher: String,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl Sealed {
+    /// 判断文件内容是否为加密后的凭据
+    pub(crate) fn detect(content: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(content).ok().filter(|sealed| sealed.cipher == CIPHER)
+    }
+}
+
+/// 用口令派生的密钥加密，每次加密都使用新的盐与随机数
+pub(crate) fn seal(plaintext: &[u8], secret: &[u8]) -> Result<Sealed> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = derive_cipher(secret, &salt)?;
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| BiliLiveError::CryptoError("加密失败".to_string()))?;
+    Ok(Sealed {
+        version: VERSION,
+        kdf: KDF.to_string(),
+        cipher: CIPHER.to_string(),
+        salt: to_hex(&salt),
+        nonce: to_hex(&nonce),
+        ciphertext: to_hex(&ciphertext),
+    })
+}
+
+/// 解密并校验完整性，口令错误与内容被篡改都会返回错误
+pub(crate) fn open(sealed: &Sealed, secret: &[u8]) -> Result<Vec<u8>> {
+    if sealed.version != VERSION || sealed.kdf != KDF {
+        return Err(BiliLiveError::CryptoError(format!("不支持的加密格式: {} v{}", sealed.kdf, sealed.version)));
+    }
+    let invalid = || BiliLiveError::CryptoError("凭据文件已损坏".to_string());
+    let salt = from_hex(&sealed.salt).ok_or_else(invalid)?;
+    let nonce = from_hex(&sealed.nonce).filter(|n| n.len() == 24).ok_or_else(invalid)?;
+    let ciphertext = from_hex(&sealed.ciphertext).ok_or_else(invalid)?;
+    derive_cipher(secret, &salt)?
+        .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| BiliLiveError::CryptoError("口令错误或凭据文件已被篡改".to_string()))
+}
+
+fn derive_cipher(secret: &[u8], salt: &[u8]) -> Result<XChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(secret, salt, &mut key)
+        .map_err(|e| BiliLiveError::CryptoError(format!("密钥派生失败: {}", e)))?;
+    Ok(XChaCha20Poly1305::new(&key.into()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -1,9 +1,14 @@
 use thiserror::Error;
 
+/// 库与命令行程序共用的错误类型
+///
+/// 以后可能新增变体，匹配时需要保留通配分支。
 #[derive(Error, Debug)]
+#[non_exhaustive]
+#[allow(clippy::enum_variant_names)]
 pub enum BiliLiveError {
     #[error("网络请求失败: {0}")]
-    NetworkError(#[from] minreq::Error),
+    NetworkError(#[from] ureq::Error),
     
     #[error("JSON解析失败: {0}")]
     JsonError(#[from] serde_json::Error),
@@ -17,29 +22,44 @@ pub enum BiliLiveError {
     #[error("二维码生成失败: {0}")]
     QrCodeError(#[from] qrcode::types::QrError),
     
+    #[error("配置文件解析失败: {0}")]
+    ConfigError(#[from] toml::de::Error),
+    
     #[error("图像处理失败: {0}")]
     ImageError(#[from] image::ImageError),
     
     #[error("Cookie文件不存在或无效")]
-    _CookieError,
+    CookieError,
     
     #[error("登录状态异常: {0}")]
-    _LoginError(String),
+    LoginError(String),
+    
+    #[error("刷新登录凭据失败: {0}")]
+    RefreshError(String),
     
-    #[error("登录状态检查失败: {0}")]
-    _LoginStatusError(String),
+    #[error("凭据加解密失败: {0}")]
+    CryptoError(String),
     
     #[error("API返回错误: {0}")]
     ApiError(String),
     
     #[error("分区选择失败: {0}")]
-    _AreaSelectionError(String),
+    AreaSelectionError(String),
     
     #[error("用户输入错误: {0}")]
     InputError(String),
     
-    #[error("直播操作失败: {0}")]
-    _LiveError(String),
+    /// 命令行程序在 `--no-prompt` 下需要用户输入
+    #[error("需要交互输入，但当前为非交互模式: {0}")]
+    PromptRequired(String),
+    
+    /// 命令行程序无法注册退出信号的处理
+    #[error("无法监听退出信号: {0}")]
+    ShutdownError(String),
+    
+    /// 命令行程序收到退出信号后未能在限定时间内关闭直播
+    #[error("关闭直播超时（{0} 秒），请运行 bili_live stop 重试")]
+    ShutdownTimeout(u64),
     
     #[error("数据解析失败: {0}")]
     ParseError(String),
@@ -0,0 +1,57 @@
+use std::io;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::config::HookConfig;
+use crate::{user_info, user_warning};
+
+/// 开播后在后台执行 `on_start`，不等待其结束
+pub fn on_start(hooks: &HookConfig, vars: &[(&str, String)]) {
+    let Some(command) = &hooks.on_start else {
+        return;
+    };
+    match spawn(command, vars) {
+        Ok(_) => user_info!("已执行开播钩子: {}", command),
+        Err(e) => user_warning!("执行开播钩子失败: {}", e),
+    }
+}
+
+/// 下播后执行 `on_stop` 并最多等待 `timeout`，超时则终止钩子，失败不影响下播结果
+pub fn on_stop(hooks: &HookConfig, vars: &[(&str, String)], timeout: Duration) {
+    let Some(command) = &hooks.on_stop else {
+        return;
+    };
+    match spawn(command, vars).and_then(|child| wait_timeout(child, timeout)) {
+        Ok(Some(status)) if status.success() => user_info!("已执行下播钩子: {}", command),
+        Ok(Some(status)) => user_warning!("下播钩子异常退出: {}", status),
+        Ok(None) => user_warning!("下播钩子在下播超时前没有结束，已终止"),
+        Err(e) => user_warning!("执行下播钩子失败: {}", e),
+    }
+}
+
+/// 等待子进程结束，超时后将其终止并返回 `None`
+fn wait_timeout(mut child: Child, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            child.kill()?;
+            child.wait()?;
+            return Ok(None);
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// 通过系统 shell 执行命令，变量以 `BILI_LIVE_` 前缀的环境变量传入
+fn spawn(command: &str, vars: &[(&str, String)]) -> io::Result<Child> {
+    let mut shell = if cfg!(windows) { Command::new("cmd") } else { Command::new("sh") };
+    shell.arg(if cfg!(windows) { "/C" } else { "-c" }).arg(command);
+    for (key, value) in vars {
+        shell.env(format!("BILI_LIVE_{}", key), value);
+    }
+    // 标准输出留给机器可读的结果，钩子的输出一律写到stderr
+    shell.stdin(Stdio::null()).stdout(io::stderr()).spawn()
+}
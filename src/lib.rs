@@ -0,0 +1,38 @@
+//! 哔哩哔哩直播推流码获取工具的核心库
+//!
+//! 提供扫码登录、开播、下播与直播统计等接口的封装，命令行程序 `bili_live` 也基于本库实现。
+//!
+//! ```no_run
+//! use bili_live::{ApiBase, BilibiliClient, Cookies};
+//!
+//! # fn main() -> bili_live::Result<()> {
+//! let mut client = BilibiliClient::new(ApiBase::default());
+//! client.set_cookies(Cookies::load_encrypted("cookies.json", b"passphrase")?);
+//!
+//! let live = client.start_live(235)?;
+//! println!("{}{}", live.rtmp.addr, live.rtmp.code);
+//!
+//! client.stop_live()?;
+//! let stats = client.live_stats(&live.live_key)?;
+//! println!("弹幕数: {}", stats.danmu_num);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod client;
+pub mod cookies;
+mod crypto;
+pub mod error;
+mod refresh;
+pub mod session;
+pub mod types;
+
+pub use client::{ApiBase, BilibiliClient};
+pub use cookies::{CookieValue, Cookies};
+pub use error::{BiliLiveError, Result};
+pub use session::{AreaSegment, LiveSession};
+pub use types::{
+    Area, AreaListFetch, AreaParent, CookieInfoData, LiveArea, NavData, QrCodeData, QrPollData, RecentArea,
+    RefreshData, RoomDetailData, RoomInfoData, RoomNewsData, Rtmp, SpiData, StartLiveData, StopLiveData,
+    UploadImageData, QR_STATUS,
+};
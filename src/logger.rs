@@ -2,7 +2,8 @@ use env_logger::{Builder, Target};
 use log::LevelFilter;
 use std::io::Write;
 
-pub fn init_logger() {
+/// 初始化日志系统，`target` 决定日志写入标准输出还是标准错误
+pub fn init_logger(target: Target, level: LevelFilter) {
     let mut builder = Builder::new();
     
     // 设置日志格式
@@ -25,11 +26,11 @@ pub fn init_logger() {
         )
     });
     
-    // 设置默认日志级别
-    builder.filter(None, LevelFilter::Info);
+    // 设置日志级别
+    builder.filter(None, level);
     
-    // 设置目标为stdout
-    builder.target(Target::Stdout);
+    // 机器可读的输出模式下日志改写到stderr，避免污染stdout
+    builder.target(target);
     
     // 初始化日志系统
     builder.init();
@@ -69,8 +70,8 @@ macro_rules! user_prompt {
     ($($arg:tt)*) => {
         {
             use std::io::Write;
-            print!("📺 {}", format!($($arg)*));
-            std::io::stdout().flush().unwrap();
+            eprint!("📺 {}", format!($($arg)*));
+            std::io::stderr().flush().unwrap();
         }
     };
 }
@@ -80,8 +81,8 @@ macro_rules! user_input_prompt {
     ($($arg:tt)*) => {
         {
             use std::io::Write;
-            print!("🎯 {}", format!($($arg)*));
-            std::io::stdout().flush().unwrap();
+            eprint!("🎯 {}", format!($($arg)*));
+            std::io::stderr().flush().unwrap();
         }
     };
 } 
\ No newline at end of file
@@ -1,76 +1,455 @@
+mod area_cache;
+mod area_match;
+mod commands;
+mod config;
+mod cover;
+mod credentials;
+mod hooks;
+mod output;
+mod paths;
+mod profile;
+mod shutdown;
+mod tui;
 mod utils;
-mod error;
 mod logger;
 
-use error::{BiliLiveError, Result};
+use bili_live::client::DEFAULT_TIMEOUT;
+use bili_live::{ApiBase, BiliLiveError, BilibiliClient};
+use commands::{Context, RecoverAction, RoomUpdate};
+use config::{ApiSettings, CacheSettings, Config, Settings, TimeoutSettings};
+use credentials::CredentialStore;
 use crate::logger::init_logger;
-use clap::Parser;
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{Args as ClapArgs, CommandFactory, FromArgMatches, Parser, Subcommand};
+use env_logger::Target;
+use log::LevelFilter;
+use output::OutputFormat;
+use paths::DataDirs;
+use profile::ProfileIndex;
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// 未指定子命令时等同于 start
+    #[command(flatten)]
+    start: StartArgs,
+
+    /// 非交互模式
+    #[arg(long, global = true, help = "禁止任何交互式输入，需要输入时直接报错退出，适用于脚本与服务")]
+    no_prompt: bool,
+
+    /// 输出格式
+    #[arg(long, global = true, value_enum, env = "BILI_LIVE_OUTPUT", help = "输出格式：text 为日志，json 为逐行 JSON，env 为 shell 变量赋值；后两者会将日志改写到stderr，默认为 text")]
+    output: Option<OutputFormat>,
+
+    /// 日志级别
+    #[arg(long, global = true, value_name = "LEVEL", env = "BILI_LIVE_LOG_LEVEL", help = "日志级别：off、error、warn、info、debug 或 trace，默认为 info")]
+    log_level: Option<LevelFilter>,
+
+    /// 使用的账号
+    #[arg(long, global = true, env = "BILI_LIVE_PROFILE", help = "使用指定账号的凭据，默认为 bili_live profile default 设置的账号")]
+    profile: Option<String>,
+
+    /// 配置文件路径
+    #[arg(long, global = true, env = "BILI_LIVE_CONFIG", help = "配置文件路径，默认使用当前目录或配置目录下的 bili_live.toml")]
+    config: Option<PathBuf>,
+
+    /// 配置目录
+    #[arg(long, global = true, value_name = "DIR", env = "BILI_LIVE_CONFIG_DIR", help = "存放账号与登录凭据的目录，默认为系统配置目录下的 bili_live")]
+    config_dir: Option<PathBuf>,
+
+    /// 状态目录
+    #[arg(long, global = true, value_name = "DIR", env = "BILI_LIVE_STATE_DIR", help = "存放直播状态的目录，默认为系统状态目录下的 bili_live")]
+    state_dir: Option<PathBuf>,
+
+    /// 密钥文件
+    #[arg(long, global = true, value_name = "FILE", env = "BILI_LIVE_KEY_FILE", help = "用文件内容代替口令加解密登录凭据，未指定时使用环境变量 BILI_LIVE_PASSPHRASE 或交互输入的口令")]
+    key_file: Option<PathBuf>,
+
+    /// 明文保存凭据
+    #[arg(long, global = true, env = "BILI_LIVE_INSECURE_PLAINTEXT", value_parser = clap::builder::BoolishValueParser::new(), help = "不加密，以明文保存登录凭据（不推荐，仅用于兼容已有部署）")]
+    insecure_plaintext: bool,
+
+    /// 登录接口地址
+    #[arg(long, global = true, env = "BILI_LIVE_PASSPORT_URL", help = "登录接口地址，默认为 https://passport.bilibili.com")]
+    passport_url: Option<String>,
+
+    /// 主站接口地址
+    #[arg(long, global = true, env = "BILI_LIVE_WEB_URL", help = "主站接口地址，默认为 https://api.bilibili.com")]
+    web_url: Option<String>,
+
+    /// 直播接口地址
+    #[arg(long, global = true, env = "BILI_LIVE_LIVE_URL", help = "直播接口地址，默认为 https://api.live.bilibili.com")]
+    live_url: Option<String>,
+
+    /// 主站页面地址
+    #[arg(long, global = true, env = "BILI_LIVE_WWW_URL", help = "主站页面地址，刷新登录凭据时使用，默认为 https://www.bilibili.com")]
+    www_url: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 扫码登录并保存凭据
+    Login,
+    /// 退出登录并删除本地凭据
+    Logout,
+    /// 查看登录状态与直播间是否正在直播
+    Status,
+    /// 开播，并在收到 Ctrl+C 或终止信号后下播（默认命令）
+    Start(StartArgs),
+    /// 关闭直播间的直播，可在开播进程意外退出后使用
+    Stop {
+        /// 本场直播的 live_key，默认使用开播时记录在本地的场次
+        #[arg(long)]
+        live_key: Option<String>,
+    },
+    /// 直播中切换直播间分区，不中断推流
+    SwitchArea {
+        /// 要切换到的二级分区ID，省略时交互选择
+        #[arg(long, value_name = "ID")]
+        area: Option<u32>,
+    },
+    /// 列出、搜索或查看直播分区，优先使用本地缓存（默认列出全部分区）
+    Areas {
+        /// 忽略未过期的缓存，重新获取分区列表
+        #[arg(long, global = true)]
+        refresh: bool,
+        #[command(subcommand)]
+        command: Option<AreasCommand>,
+    },
+    /// 查看或修改直播间标题，直播中也可修改
+    Title {
+        /// 新的标题，省略时显示当前标题
+        title: Option<String>,
+    },
+    /// 查询指定场次的直播统计
+    Stats {
+        /// 开播时返回的 live_key
+        live_key: String,
+    },
+    /// 查看或修改直播间的公告、简介与标签
+    #[command(subcommand)]
+    Room(RoomCommand),
+    /// 设置直播间封面
+    #[command(subcommand)]
+    Cover(CoverCommand),
+    /// 管理多个账号
+    #[command(subcommand)]
+    Profile(ProfileCommand),
+    /// 显示合并命令行、环境变量与配置文件后实际生效的设置
+    Config,
+}
+
+#[derive(Subcommand)]
+enum AreasCommand {
+    /// 列出全部直播分区
+    List,
+    /// 按名称或ID搜索分区
+    Search {
+        /// 关键字，匹配一级或二级分区名称，不区分大小写
+        keyword: String,
+    },
+    /// 显示指定ID的分区
+    Show {
+        /// 二级分区ID
+        id: u32,
+    },
+    /// 管理当前账号收藏的分区，选择分区时会优先列出
+    #[command(subcommand)]
+    Favorite(FavoriteCommand),
+}
+
+#[derive(Subcommand)]
+enum FavoriteCommand {
+    /// 列出收藏的分区
+    List,
+    /// 收藏分区
+    Add {
+        /// 二级分区ID
+        id: u32,
+    },
+    /// 取消收藏分区
+    Remove {
+        /// 二级分区ID
+        id: u32,
+    },
+}
+
+#[derive(Subcommand)]
+enum RoomCommand {
+    /// 显示直播间的标题、公告、简介与标签
+    Show,
+    /// 一次修改直播间的多项信息，未指定的项保持不变
+    Set {
+        /// 直播间标题
+        #[arg(long)]
+        title: Option<String>,
+        /// 直播间公告，传入空字符串清空
+        #[arg(long)]
+        announcement: Option<String>,
+        /// 直播间简介
+        #[arg(long)]
+        intro: Option<String>,
+        /// 以逗号分隔的直播间标签，传入空字符串清空
+        #[arg(long, value_delimiter = ',')]
+        tags: Option<Vec<String>>,
+    },
+}
+
+#[derive(Subcommand)]
+enum CoverCommand {
+    /// 将图片裁剪为 16:9 并缩放到 1280x720 后上传为直播间封面
+    Set {
+        /// PNG 或 JPEG 图片
+        path: PathBuf,
+        /// 只处理图片并保存到本地，不上传
+        #[arg(long)]
+        dry_run: bool,
+        /// 试运行时处理后图片的保存位置，默认为原图同目录下的 <文件名>_cover.jpg
+        #[arg(long, value_name = "FILE", requires = "dry_run")]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileCommand {
+    /// 列出全部账号，* 标记默认账号
+    List,
+    /// 扫码登录并添加账号
+    Add {
+        /// 账号名，只能包含字母、数字、- 和 _
+        name: String,
+        /// 该账号的偏好分区，开播时未指定分区则直接使用
+        #[arg(long, value_name = "ID")]
+        area: Option<u32>,
+    },
+    /// 删除账号的本地凭据
+    Remove {
+        name: String,
+    },
+    /// 设置未指定 --profile 时使用的账号
+    Default {
+        name: String,
+    },
+    /// 设置账号的偏好分区，省略分区ID则清除
+    SetArea {
+        name: String,
+        area: Option<u32>,
+    },
+}
+
+#[derive(ClapArgs, Clone)]
+pub struct StartArgs {
     /// 直接显示完整的推流码（不打码）
-    #[arg(long, help = "显示完整的推流码，不进行打码处理")]
-    show_full_code: bool,
+    #[arg(long, overrides_with = "no_show_full_code", help = "显示完整的推流码，不进行打码处理")]
+    pub show_full_code: bool,
+
+    /// 推流码打码显示，覆盖配置文件中的 show_full_code
+    #[arg(long, overrides_with = "show_full_code", help = "推流码打码显示，覆盖配置文件中的 show_full_code = true")]
+    pub no_show_full_code: bool,
+
+    /// 开播后复制推流码到剪贴板
+    #[arg(long, overrides_with = "no_clipboard", help = "开播后将推流码复制到剪贴板，覆盖配置文件中的 clipboard = false")]
+    pub clipboard: bool,
+
+    /// 开播后不复制推流码
+    #[arg(long, overrides_with = "clipboard", help = "开播后不将推流码复制到剪贴板")]
+    pub no_clipboard: bool,
+
+    /// 直接指定直播分区ID
+    #[arg(long, value_name = "ID", help = "直接使用指定的二级分区ID开播，跳过分区选择")]
+    pub area: Option<u32>,
+
+    /// 开播前设置的直播间标题
+    #[arg(long, value_name = "TITLE", help = "开播前将直播间标题修改为指定内容，默认使用配置文件中的 title")]
+    pub title: Option<String>,
+
+    /// 使用全屏终端界面
+    #[arg(long, help = "使用全屏终端界面选择分区并显示直播状态，默认仍为日志输出")]
+    pub tui: bool,
+
+    /// 直接使用上次直播的分区
+    #[arg(long, conflicts_with = "area", help = "直接使用上次直播的分区开播，不再询问")]
+    pub use_last_area: bool,
+
+    /// 检测到上次未关闭的直播时的处理方式
+    #[arg(long, value_enum, value_name = "ACTION", help = "检测到上次未关闭的直播时如何处理，不再询问")]
+    pub recover: Option<RecoverAction>,
+
+    /// 收到退出信号后关闭直播的超时时间
+    #[arg(long, value_name = "SECS", env = "BILI_LIVE_SHUTDOWN_TIMEOUT", help = "收到退出信号后关闭直播的最长等待秒数，超时以退出码 124 退出，默认为 15")]
+    pub shutdown_timeout: Option<u64>,
+}
+
+impl StartArgs {
+    /// 命令行上是否指定了显示完整推流码，未指定时使用配置文件中的设置
+    pub fn show_full_code_flag(&self) -> Option<bool> {
+        toggle(self.show_full_code, self.no_show_full_code)
+    }
+
+    /// 命令行上是否指定了复制推流码，未指定时使用配置文件中的设置
+    pub fn clipboard_flag(&self) -> Option<bool> {
+        toggle(self.clipboard, self.no_clipboard)
+    }
+}
+
+/// 成对的 `--xxx` / `--no-xxx` 开关，后出现的覆盖先出现的，都未指定时为 `None`
+fn toggle(on: bool, off: bool) -> Option<bool> {
+    match (on, off) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
+/// 按 命令行/环境变量 > 配置文件 > 默认值 的优先级确定实际生效的设置
+fn settings(args: &Args, config: Config) -> Settings {
+    let default = ApiBase::default();
+    let pick = |arg: &Option<String>, configured: Option<String>, fallback: String| {
+        arg.clone()
+            .or(configured)
+            .map(|url| url.trim_end_matches('/').to_string())
+            .unwrap_or(fallback)
+    };
+    // 开播参数既可以直接给出，也可以放在 start 子命令之后
+    let start = match &args.command {
+        Some(Command::Start(start)) => start,
+        _ => &args.start,
+    };
+    Settings {
+        area: config.area,
+        title: config.title,
+        show_full_code: config.show_full_code.unwrap_or(false),
+        clipboard: config.clipboard.unwrap_or(true),
+        output: args.output.or(config.output).unwrap_or_default(),
+        log_level: args.log_level.or(config.log_level).unwrap_or(LevelFilter::Info),
+        api: ApiSettings {
+            passport_url: pick(&args.passport_url, config.api.passport_url, default.passport),
+            web_url: pick(&args.web_url, config.api.web_url, default.web),
+            live_url: pick(&args.live_url, config.api.live_url, default.live),
+            www_url: pick(&args.www_url, config.api.www_url, default.www),
+        },
+        timeouts: TimeoutSettings {
+            http: config.timeouts.http.unwrap_or(DEFAULT_TIMEOUT.as_secs()),
+            shutdown: start.shutdown_timeout.or(config.timeouts.shutdown).unwrap_or(shutdown::DEFAULT_SHUTDOWN_TIMEOUT),
+        },
+        cache: CacheSettings {
+            area_ttl: config.cache.area_ttl.unwrap_or(area_cache::DEFAULT_AREA_TTL),
+        },
+        hooks: config.hooks,
+    }
+}
+
+/// 解析命令行参数，开播参数只能在未指定子命令时使用，全局参数则可放在子命令前后
+fn parse_args() -> Args {
+    let matches = Args::command().get_matches();
+    if let Some(subcommand) = matches.subcommand_name() {
+        let start_args = StartArgs::augment_args(clap::Command::new("start"));
+        for arg in start_args.get_arguments() {
+            if matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine) {
+                Args::command()
+                    .error(
+                        ErrorKind::ArgumentConflict,
+                        format!(
+                            "参数 '--{}' 不能与子命令 '{}' 同时使用",
+                            arg.get_long().unwrap_or(arg.get_id().as_str()),
+                            subcommand
+                        ),
+                    )
+                    .exit();
+            }
+        }
+    }
+    Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit())
 }
 
 fn main() {
-    let args = Args::parse();
+    let args = parse_args();
+    let dirs = DataDirs::resolve(args.config_dir.clone(), args.state_dir.clone());
+    let config_file = args.config.clone().unwrap_or_else(|| dirs.config_file());
+
+    // 配置文件有误时仍按命令行参数初始化日志，以便报告错误
+    let (config, config_error) = match Config::load(&config_file) {
+        Ok(config) => (config, None),
+        Err(e) => (Config::default(), Some(e)),
+    };
+    let settings = settings(&args, config);
     
     // 初始化日志系统
-    init_logger();
+    init_logger(
+        if settings.output.is_machine_readable() { Target::Stderr } else { Target::Stdout },
+        settings.log_level,
+    );
     
-    if let Err(e) = run(args) {
+    let result = match config_error {
+        Some(e) => Err(e),
+        None => run(args, dirs, config_file, settings),
+    };
+    if let Err(e) = result {
         user_error!("程序执行失败: {}", e);
-        std::process::exit(1);
+        std::process::exit(match e {
+            BiliLiveError::ShutdownTimeout(_) => shutdown::EXIT_TIMEOUT,
+            _ => 1,
+        });
     }
 }
 
-fn run(args: Args) -> Result<()> {
-    let check_status = utils::check_status()?;
-
-    if !check_status {
-        user_info!("需要登录，开始登录流程...");
-        utils::start_login()?;
-        user_success!("登录成功！");
-    } else {
-        user_success!("登录状态正常");
-    }
-
-    user_prompt!("是否使用上次直播的分区？(直接回车或输入y使用默认，输入n选择新分区)");
-    let mut input = String::new();
-    std::io::stdin()
-        .read_line(&mut input)
-        .map_err(|e| BiliLiveError::InputError(format!("读取用户输入失败: {}", e)))?;
-
-    let area_id = if input.trim().is_empty() || input.trim().to_lowercase() == "y" {
-        let (id, name) = utils::get_recent_live()?;
-        user_success!("使用上次的分区: {} - {}", name, id);
-        id.parse().map_err(|e| BiliLiveError::ParseError(format!("分区ID转换失败: {}", e)))?
-    } else {
-        user_info!("选择合适的直播分区！");
-        utils::get_area_choice()?
+fn run(args: Args, dirs: DataDirs, config_file: PathBuf, settings: Settings) -> bili_live::Result<()> {
+    dirs.migrate_legacy_files()?;
+    let profile = ProfileIndex::load(&dirs)?.profile(&dirs, args.profile.as_deref())?;
+    let api = ApiBase {
+        passport: settings.api.passport_url.clone(),
+        web: settings.api.web_url.clone(),
+        live: settings.api.live_url.clone(),
+        www: settings.api.www_url.clone(),
+    };
+    let ctx = Context {
+        client: BilibiliClient::new(api).with_timeout(Duration::from_secs(settings.timeouts.http)),
+        output: settings.output,
+        no_prompt: args.no_prompt,
+        credentials: CredentialStore::new(args.key_file.clone(), args.insecure_plaintext, args.no_prompt),
+        dirs,
+        profile,
+        settings,
     };
 
-    // 开始直播
-    user_info!("开始直播！");
-    let live_id = utils::start_live(&area_id.to_string(), args.show_full_code)?;
-
-    user_info!("请在本程序中按 Ctrl+C 关闭直播！否则直播将不会关闭！");
-
-    // 监听程序退出信号
-    let _ = ctrlc::set_handler(move || {
-        user_info!("监听到 Ctrl+C，准备关闭直播！");
-        if let Err(e) = utils::stop_live(live_id) {
-            user_error!("停止直播失败: {}", e);
-        } else {
-            user_success!("直播已关闭！");
+    match args.command {
+        None => commands::start(ctx, &args.start),
+        Some(Command::Start(start)) => commands::start(ctx, &start),
+        Some(Command::Login) => commands::login(ctx),
+        Some(Command::Logout) => commands::logout(ctx),
+        Some(Command::Status) => commands::status(ctx),
+        Some(Command::Stop { live_key }) => commands::stop(ctx, live_key.as_deref()),
+        Some(Command::SwitchArea { area }) => commands::switch_area(ctx, area),
+        Some(Command::Areas { refresh, command }) => match command {
+            None | Some(AreasCommand::List) => commands::areas_list(ctx, refresh),
+            Some(AreasCommand::Search { keyword }) => commands::areas_search(ctx, &keyword, refresh),
+            Some(AreasCommand::Show { id }) => commands::areas_show(ctx, id, refresh),
+            Some(AreasCommand::Favorite(FavoriteCommand::List)) => commands::favorite_list(ctx),
+            Some(AreasCommand::Favorite(FavoriteCommand::Add { id })) => commands::favorite_add(ctx, id, refresh),
+            Some(AreasCommand::Favorite(FavoriteCommand::Remove { id })) => commands::favorite_remove(ctx, id),
+        },
+        Some(Command::Title { title }) => commands::title(ctx, title.as_deref()),
+        Some(Command::Stats { live_key }) => commands::stats(ctx, &live_key),
+        Some(Command::Room(RoomCommand::Show)) => commands::room_show(ctx),
+        Some(Command::Room(RoomCommand::Set { title, announcement, intro, tags })) => commands::room_set(
+            ctx,
+            RoomUpdate { title, announcement, intro, tags },
+        ),
+        Some(Command::Cover(CoverCommand::Set { path, dry_run, out })) => {
+            commands::cover_set(ctx, &path, dry_run.then(|| out.unwrap_or_else(|| cover::dry_run_path(&path))))
         }
-        std::process::exit(0);
-    });
-
-    // 使程序保持运行状态
-    loop {
-        std::thread::sleep(std::time::Duration::from_secs(1));
+        Some(Command::Profile(ProfileCommand::List)) => commands::profile_list(ctx),
+        Some(Command::Profile(ProfileCommand::Add { name, area })) => commands::profile_add(ctx, &name, area),
+        Some(Command::Profile(ProfileCommand::Remove { name })) => commands::profile_remove(ctx, &name),
+        Some(Command::Profile(ProfileCommand::Default { name })) => commands::profile_default(ctx, &name),
+        Some(Command::Profile(ProfileCommand::SetArea { name, area })) => commands::profile_set_area(ctx, &name, area),
+        Some(Command::Config) => commands::config(ctx, &config_file),
     }
 }
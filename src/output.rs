@@ -0,0 +1,379 @@
+use bili_live::{AreaParent, AreaSegment, BiliLiveError, LiveArea, LiveSession, StartLiveData, StopLiveData};
+use crate::config::Settings;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// 开播结果的输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// 面向人的日志输出
+    #[default]
+    Text,
+    /// 每个事件输出一行 JSON
+    Json,
+    /// 输出可被 shell `eval` 的环境变量赋值语句
+    Env,
+}
+
+#[derive(Serialize)]
+struct StartedReport<'a> {
+    event: &'static str,
+    room_id: i64,
+    area: &'a LiveArea,
+    rtmp_addr: &'a str,
+    rtmp_code: &'a str,
+    live_key: &'a str,
+}
+
+#[derive(Serialize)]
+struct ResumedReport<'a> {
+    event: &'static str,
+    #[serde(flatten)]
+    session: &'a LiveSession,
+}
+
+#[derive(Serialize)]
+struct StatsReport<'a> {
+    event: &'static str,
+    room_id: i64,
+    live_key: Option<&'a str>,
+    stats: Option<&'a StopLiveData>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    areas: &'a [AreaSegment],
+}
+
+/// 登录与直播间状态
+#[derive(Debug, Default, Serialize)]
+pub struct StatusReport {
+    pub profile: String,
+    pub logged_in: bool,
+    pub mid: Option<i64>,
+    pub uname: Option<String>,
+    pub room_id: Option<i64>,
+    pub live: bool,
+    pub title: Option<String>,
+}
+
+/// 直播间的标题、公告、简介与标签
+#[derive(Debug, Serialize)]
+pub struct RoomReport {
+    pub room_id: i64,
+    pub title: String,
+    pub announcement: String,
+    pub description: String,
+    pub tags: Vec<String>,
+}
+
+/// 单个二级分区及其所属的一级分区
+#[derive(Debug, Serialize)]
+pub struct AreaReport {
+    pub id: u32,
+    pub name: String,
+    pub parent_name: String,
+}
+
+/// 账号列表中的一项
+#[derive(Debug, Serialize)]
+pub struct ProfileReport {
+    pub name: String,
+    pub default: bool,
+    pub logged_in: bool,
+    pub preferred_area: Option<LiveArea>,
+}
+
+/// 按 shell 规则用单引号包裹
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// 开播信息对应的变量，env 输出与钩子命令共用
+pub fn started_vars(room_id: i64, area: &LiveArea, live: &StartLiveData) -> Vec<(&'static str, String)> {
+    vec![
+        ("ROOM_ID", room_id.to_string()),
+        ("AREA_ID", area.id.to_string()),
+        ("AREA_NAME", area.name.clone().unwrap_or_default()),
+        ("RTMP_ADDR", live.rtmp.addr.clone()),
+        ("RTMP_CODE", live.rtmp.code.clone()),
+        ("LIVE_KEY", live.live_key.clone()),
+    ]
+}
+
+/// 下播统计对应的变量，env 输出与钩子命令共用，`AREAS` 为本场依次使用过的分区ID
+pub fn stats_vars(
+    room_id: i64,
+    live_key: Option<&str>,
+    stats: Option<&StopLiveData>,
+    areas: &[AreaSegment],
+) -> Vec<(&'static str, String)> {
+    let mut pairs = vec![("ROOM_ID", room_id.to_string())];
+    if let Some(live_key) = live_key {
+        pairs.push(("LIVE_KEY", live_key.to_string()));
+    }
+    if !areas.is_empty() {
+        let ids: Vec<String> = areas.iter().map(|segment| segment.area.id.to_string()).collect();
+        pairs.push(("AREAS", ids.join(",")));
+    }
+    if let Some(stats) = stats {
+        pairs.extend([
+            ("STATS_ADD_FANS", stats.add_fans.to_string()),
+            ("STATS_DANMU_NUM", stats.danmu_num.to_string()),
+            ("STATS_HAMSTER_RMB", stats.hamster_rmb.to_string()),
+            ("STATS_LIVE_TIME", stats.live_time.to_string()),
+            ("STATS_MAX_ONLINE", stats.max_online.to_string()),
+            ("STATS_NEW_FANS_CLUB", stats.new_fans_club.to_string()),
+            ("STATS_WATCHED_COUNT", stats.watched_count.to_string()),
+        ]);
+    }
+    pairs
+}
+
+fn print_env(pairs: &[(&str, String)]) {
+    for (key, value) in pairs {
+        println!("BILI_LIVE_{}={}", key, shell_quote(value));
+    }
+}
+
+impl OutputFormat {
+    /// 日志是否需要让出标准输出
+    pub fn is_machine_readable(self) -> bool {
+        self != OutputFormat::Text
+    }
+
+    /// 输出开播结果，文本模式下已由日志展示，不再重复输出
+    pub fn emit_started(self, room_id: i64, area: &LiveArea, live: &StartLiveData) -> bili_live::Result<()> {
+        match self {
+            OutputFormat::Text => {}
+            OutputFormat::Json => {
+                let report = StartedReport {
+                    event: "started",
+                    room_id,
+                    area,
+                    rtmp_addr: &live.rtmp.addr,
+                    rtmp_code: &live.rtmp.code,
+                    live_key: &live.live_key,
+                };
+                println!("{}", serde_json::to_string(&report)?);
+            }
+            OutputFormat::Env => print_env(&started_vars(room_id, area, live)),
+        }
+        Ok(())
+    }
+
+    /// 输出继续上次直播的结果，推流码沿用上次获取的，不再输出
+    pub fn emit_resumed(self, session: &LiveSession) -> bili_live::Result<()> {
+        match self {
+            OutputFormat::Text => {}
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string(&ResumedReport { event: "resumed", session })?);
+            }
+            OutputFormat::Env => print_env(&[
+                ("ROOM_ID", session.room_id.to_string()),
+                ("AREA_ID", session.area.id.to_string()),
+                ("AREA_NAME", session.area.name.clone().unwrap_or_default()),
+                ("LIVE_KEY", session.live_key.clone()),
+            ]),
+        }
+        Ok(())
+    }
+
+    /// 输出下播结果，未提供 live_key 时没有统计信息，`areas` 为本地记录的各分区片段
+    pub fn emit_stopped(
+        self,
+        room_id: i64,
+        live_key: Option<&str>,
+        stats: Option<&StopLiveData>,
+        areas: &[AreaSegment],
+    ) -> bili_live::Result<()> {
+        self.emit_stats_report(StatsReport { event: "stopped", room_id, live_key, stats, areas })
+    }
+
+    /// 输出指定场次的直播统计
+    pub fn emit_stats(self, room_id: i64, live_key: &str, stats: &StopLiveData) -> bili_live::Result<()> {
+        self.emit_stats_report(StatsReport {
+            event: "stats",
+            room_id,
+            live_key: Some(live_key),
+            stats: Some(stats),
+            areas: &[],
+        })
+    }
+
+    fn emit_stats_report(self, report: StatsReport) -> bili_live::Result<()> {
+        match self {
+            OutputFormat::Text => {}
+            OutputFormat::Json => println!("{}", serde_json::to_string(&report)?),
+            OutputFormat::Env => print_env(&stats_vars(report.room_id, report.live_key, report.stats, report.areas)),
+        }
+        Ok(())
+    }
+
+    /// 输出登录与直播间状态
+    pub fn emit_status(self, report: &StatusReport) -> bili_live::Result<()> {
+        match self {
+            OutputFormat::Text => {}
+            OutputFormat::Json => println!("{}", serde_json::to_string(report)?),
+            OutputFormat::Env => {
+                let mut pairs = vec![
+                    ("PROFILE", report.profile.clone()),
+                    ("LOGGED_IN", report.logged_in.to_string()),
+                    ("LIVE", report.live.to_string()),
+                ];
+                if let Some(room_id) = report.room_id {
+                    pairs.push(("ROOM_ID", room_id.to_string()));
+                }
+                if let Some(title) = &report.title {
+                    pairs.push(("TITLE", title.clone()));
+                }
+                print_env(&pairs);
+            }
+        }
+        Ok(())
+    }
+
+    /// 输出直播中切换后的分区
+    pub fn emit_area_switched(self, room_id: i64, area: &LiveArea) -> bili_live::Result<()> {
+        match self {
+            OutputFormat::Text => {}
+            OutputFormat::Json => {
+                println!("{}", serde_json::json!({ "event": "area_switched", "room_id": room_id, "area": area }));
+            }
+            OutputFormat::Env => print_env(&[
+                ("ROOM_ID", room_id.to_string()),
+                ("AREA_ID", area.id.to_string()),
+                ("AREA_NAME", area.name.clone().unwrap_or_default()),
+            ]),
+        }
+        Ok(())
+    }
+
+    /// 输出直播间标题
+    pub fn emit_title(self, room_id: i64, title: &str) -> bili_live::Result<()> {
+        match self {
+            OutputFormat::Text => {}
+            OutputFormat::Json => println!("{}", serde_json::json!({ "room_id": room_id, "title": title })),
+            OutputFormat::Env => print_env(&[("ROOM_ID", room_id.to_string()), ("TITLE", title.to_string())]),
+        }
+        Ok(())
+    }
+
+    /// 输出直播间信息
+    pub fn emit_room(self, report: &RoomReport) -> bili_live::Result<()> {
+        match self {
+            OutputFormat::Text => {}
+            OutputFormat::Json => println!("{}", serde_json::to_string(report)?),
+            OutputFormat::Env => print_env(&[
+                ("ROOM_ID", report.room_id.to_string()),
+                ("TITLE", report.title.clone()),
+                ("ANNOUNCEMENT", report.announcement.clone()),
+                ("DESCRIPTION", report.description.clone()),
+                ("TAGS", report.tags.join(",")),
+            ]),
+        }
+        Ok(())
+    }
+
+    /// 输出更新后的直播间封面地址
+    pub fn emit_cover(self, room_id: i64, cover: &str) -> bili_live::Result<()> {
+        match self {
+            OutputFormat::Text => {}
+            OutputFormat::Json => println!("{}", serde_json::json!({ "room_id": room_id, "cover": cover })),
+            OutputFormat::Env => print_env(&[("ROOM_ID", room_id.to_string()), ("COVER", cover.to_string())]),
+        }
+        Ok(())
+    }
+
+    /// 输出分区列表
+    pub fn emit_areas(self, areas: &[AreaParent]) -> bili_live::Result<()> {
+        match self {
+            OutputFormat::Text => {}
+            OutputFormat::Json => println!("{}", serde_json::to_string(areas)?),
+            OutputFormat::Env => {
+                for parent in areas {
+                    for area in &parent.list {
+                        println!("BILI_LIVE_AREA_{}={}", area.id, shell_quote(&format!("{}/{}", parent.name, area.name)));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 输出单个分区
+    pub fn emit_area(self, report: &AreaReport) -> bili_live::Result<()> {
+        match self {
+            OutputFormat::Text => {}
+            OutputFormat::Json => println!("{}", serde_json::to_string(report)?),
+            OutputFormat::Env => print_env(&[
+                ("AREA_ID", report.id.to_string()),
+                ("AREA_NAME", report.name.clone()),
+                ("AREA_PARENT_NAME", report.parent_name.clone()),
+            ]),
+        }
+        Ok(())
+    }
+
+    /// 输出收藏的分区
+    pub fn emit_favorites(self, areas: &[LiveArea]) -> bili_live::Result<()> {
+        match self {
+            OutputFormat::Text => {}
+            OutputFormat::Json => println!("{}", serde_json::to_string(areas)?),
+            OutputFormat::Env => {
+                let ids: Vec<String> = areas.iter().map(|a| a.id.to_string()).collect();
+                print_env(&[("FAVORITE_AREAS", ids.join(","))]);
+            }
+        }
+        Ok(())
+    }
+
+    /// 输出账号列表
+    pub fn emit_profiles(self, profiles: &[ProfileReport]) -> bili_live::Result<()> {
+        match self {
+            OutputFormat::Text => {}
+            OutputFormat::Json => println!("{}", serde_json::to_string(profiles)?),
+            OutputFormat::Env => {
+                let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+                let mut pairs = vec![("PROFILES", names.join(" "))];
+                if let Some(default) = profiles.iter().find(|p| p.default) {
+                    pairs.push(("DEFAULT_PROFILE", default.name.clone()));
+                }
+                print_env(&pairs);
+            }
+        }
+        Ok(())
+    }
+
+    /// 输出实际生效的设置，文本模式下为可直接写入配置文件的 TOML
+    pub fn emit_settings(self, settings: &Settings) -> bili_live::Result<()> {
+        match self {
+            OutputFormat::Text => {
+                let toml = toml::to_string_pretty(settings).map_err(|e| BiliLiveError::ParseError(e.to_string()))?;
+                print!("{}", toml);
+            }
+            OutputFormat::Json => println!("{}", serde_json::to_string(settings)?),
+            OutputFormat::Env => {
+                let mut pairs = Vec::new();
+                flatten_env(String::new(), serde_json::to_value(settings)?, &mut pairs);
+                for (key, value) in pairs {
+                    println!("BILI_LIVE_{}={}", key, shell_quote(&value));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 将嵌套的设置展开为 `API_WEB_URL` 形式的变量
+fn flatten_env(prefix: String, value: serde_json::Value, pairs: &mut Vec<(String, String)>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                let key = key.to_uppercase();
+                flatten_env(if prefix.is_empty() { key } else { format!("{}_{}", prefix, key) }, value, pairs);
+            }
+        }
+        serde_json::Value::String(value) => pairs.push((prefix, value)),
+        serde_json::Value::Null => {}
+        value => pairs.push((prefix, value.to_string())),
+    }
+}
@@ -0,0 +1,69 @@
+use bili_live::cookies::COOKIES_FILE;
+use bili_live::session::SESSION_FILE;
+use bili_live::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::config::CONFIG_FILE;
+use crate::{user_info, user_warning};
+
+const APP_DIR: &str = "bili_live";
+
+/// 凭据与运行状态的存放位置
+#[derive(Debug, Clone)]
+pub struct DataDirs {
+    /// 配置、账号列表与登录凭据，默认为 `$XDG_CONFIG_HOME/bili_live`
+    pub config: PathBuf,
+    /// 直播状态等运行时数据，默认为 `$XDG_STATE_HOME/bili_live`
+    pub state: PathBuf,
+}
+
+impl DataDirs {
+    /// 按 命令行/环境变量 > 系统默认目录 的优先级确定目录，无法确定系统目录时使用当前目录
+    pub fn resolve(config: Option<PathBuf>, state: Option<PathBuf>) -> Self {
+        let config = config
+            .or_else(|| Some(dirs::config_dir()?.join(APP_DIR)))
+            .unwrap_or_else(|| PathBuf::from("."));
+        // 只有 Linux 有独立的状态目录，其他系统放在本地数据目录下
+        let state = state
+            .or_else(|| Some(dirs::state_dir().or_else(dirs::data_local_dir)?.join(APP_DIR)))
+            .unwrap_or_else(|| config.clone());
+        Self { config, state }
+    }
+
+    /// 配置文件路径：当前目录下存在 `bili_live.toml` 时优先使用，否则使用配置目录下的
+    pub fn config_file(&self) -> PathBuf {
+        let local = PathBuf::from(CONFIG_FILE);
+        if local.exists() {
+            local
+        } else {
+            self.config.join(CONFIG_FILE)
+        }
+    }
+
+    /// 将旧版本保存在当前目录下的凭据与直播状态一次性迁移到默认账号的位置
+    pub fn migrate_legacy_files(&self) -> Result<()> {
+        for (file, dir) in [(COOKIES_FILE, &self.config), (SESSION_FILE, &self.state)] {
+            let legacy = PathBuf::from(file);
+            let target = dir.join(file);
+            // 目标已存在（包括数据目录就是当前目录）时不迁移
+            if !legacy.exists() || target.exists() {
+                continue;
+            }
+            fs::create_dir_all(dir)?;
+            move_file(&legacy, &target)?;
+            user_info!("已将当前目录下的 {} 迁移到 {}", file, target.display());
+        }
+        Ok(())
+    }
+}
+
+/// 先尝试重命名，跨文件系统时退回到复制后删除
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        if let Err(e) = fs::remove_file(from) {
+            user_warning!("删除 {} 失败: {}，请手动删除", from.display(), e);
+        }
+    }
+    Ok(())
+}
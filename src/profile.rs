@@ -0,0 +1,135 @@
+use bili_live::cookies::COOKIES_FILE;
+use bili_live::session::SESSION_FILE;
+use bili_live::{BiliLiveError, LiveArea, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use crate::paths::DataDirs;
+
+/// 未指定时使用的账号，其文件直接位于数据目录下
+pub const DEFAULT_PROFILE: &str = "default";
+pub const PROFILES_FILE: &str = "profiles.json";
+const PROFILES_DIR: &str = "profiles";
+
+/// 单个账号的偏好设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfileSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preferred_area: Option<LiveArea>,
+    /// 收藏的分区，交互选择分区时与最近使用的分区一起列出
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub favorite_areas: Vec<LiveArea>,
+}
+
+/// 账号列表与默认账号
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProfileIndex {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ProfileSettings>,
+}
+
+/// 当前使用的账号及其文件位置
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub settings: ProfileSettings,
+    config_dir: PathBuf,
+    state_dir: PathBuf,
+}
+
+/// 账号名只允许字母、数字、`-` 与 `_`，以免越出数据目录
+pub fn validate_name(name: &str) -> Result<()> {
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        Ok(())
+    } else {
+        Err(BiliLiveError::InputError(format!("无效的账号名 {:?}，只能包含字母、数字、- 和 _", name)))
+    }
+}
+
+impl ProfileIndex {
+    /// 读取账号列表，文件不存在时为空
+    pub fn load(dirs: &DataDirs) -> Result<Self> {
+        let path = dirs.config.join(PROFILES_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, dirs: &DataDirs) -> Result<()> {
+        fs::create_dir_all(&dirs.config)?;
+        fs::write(dirs.config.join(PROFILES_FILE), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// 全部账号名，已登录的默认账号即使未登记也会列出
+    pub fn names(&self, dirs: &DataDirs) -> Vec<String> {
+        let mut names: Vec<String> = self.profiles.keys().cloned().collect();
+        if !self.profiles.contains_key(DEFAULT_PROFILE) && dirs.config.join(COOKIES_FILE).exists() {
+            names.insert(0, DEFAULT_PROFILE.to_string());
+        }
+        names
+    }
+
+    /// 命令行未指定账号时使用的账号名
+    pub fn default_name(&self) -> &str {
+        self.default.as_deref().unwrap_or(DEFAULT_PROFILE)
+    }
+
+    /// 按名称取得账号，`name` 为空时使用默认账号
+    pub fn profile(&self, dirs: &DataDirs, name: Option<&str>) -> Result<Profile> {
+        let name = name.unwrap_or(self.default_name());
+        validate_name(name)?;
+        let subdir = |root: &PathBuf| {
+            if name == DEFAULT_PROFILE {
+                root.clone()
+            } else {
+                root.join(PROFILES_DIR).join(name)
+            }
+        };
+        Ok(Profile {
+            name: name.to_string(),
+            settings: self.profiles.get(name).cloned().unwrap_or_default(),
+            config_dir: subdir(&dirs.config),
+            state_dir: subdir(&dirs.state),
+        })
+    }
+}
+
+impl Profile {
+    pub fn cookies_file(&self) -> PathBuf {
+        self.config_dir.join(COOKIES_FILE)
+    }
+
+    pub fn session_file(&self) -> PathBuf {
+        self.state_dir.join(SESSION_FILE)
+    }
+
+    /// 确保账号的配置与状态目录存在
+    pub fn create_dirs(&self) -> Result<()> {
+        fs::create_dir_all(&self.config_dir)?;
+        fs::create_dir_all(&self.state_dir)?;
+        Ok(())
+    }
+
+    /// 删除账号的凭据与直播状态，非默认账号连同目录一起删除
+    pub fn remove_files(&self) -> Result<()> {
+        if self.name == DEFAULT_PROFILE {
+            for path in [self.cookies_file(), self.session_file()] {
+                if path.exists() {
+                    fs::remove_file(path)?;
+                }
+            }
+        } else {
+            for dir in [&self.config_dir, &self.state_dir] {
+                if dir.exists() {
+                    fs::remove_dir_all(dir)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
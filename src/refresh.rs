@@ -0,0 +1,34 @@
+use rsa::pkcs8::DecodePublicKey;
+use rsa::{Oaep, RsaPublicKey};
+use sha2::Sha256;
+use crate::error::{BiliLiveError, Result};
+
+/// 主站用于加密 correspondPath 的公钥
+const PUBLIC_KEY: &str = "-----BEGIN PUBLIC KEY-----
+MIGfMA0GCSqGSIb3DQEBAQUAA4GNADCBiQKBgQDLgd2OAkcGVtoE3ThUREbio0Eg
+Uc/prcajMKXvkCKFCWhJYJcLkcM2DKKcSeFpD/j6Boy538YXnR6VhcuUJOhH2x71
+nzPjfdTcqMz7djHum0qSZA0AyCBDABUqCrfNgCiJ00Ra7GmRj+YCK1NJEuewlb40
+JNrRuoEUXpabUzGB8QIDAQAB
+-----END PUBLIC KEY-----";
+
+/// 用 RSA-OAEP(SHA-256) 加密 `refresh_{毫秒时间戳}`，结果以小写十六进制表示
+pub(crate) fn correspond_path(timestamp: i64) -> Result<String> {
+    let key = RsaPublicKey::from_public_key_pem(PUBLIC_KEY)
+        .map_err(|e| BiliLiveError::RefreshError(format!("公钥无效: {}", e)))?;
+    let encrypted = key
+        .encrypt(
+            &mut rsa::rand_core::OsRng,
+            Oaep::new::<Sha256>(),
+            format!("refresh_{}", timestamp).as_bytes(),
+        )
+        .map_err(|e| BiliLiveError::RefreshError(format!("加密失败: {}", e)))?;
+    Ok(encrypted.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// 从 correspond 页面中取出 `<div id="1-name">` 的内容
+pub(crate) fn extract_refresh_csrf(html: &str) -> Option<String> {
+    let start = html.find(r#"<div id="1-name">"#)? + r#"<div id="1-name">"#.len();
+    let end = html[start..].find("</div>")? + start;
+    let refresh_csrf = html[start..end].trim();
+    (!refresh_csrf.is_empty()).then(|| refresh_csrf.to_string())
+}
@@ -0,0 +1,92 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use crate::error::Result;
+use crate::types::LiveArea;
+
+pub const SESSION_FILE: &str = "session.json";
+
+/// 直播中使用过的一个分区及切换到该分区的时间
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AreaSegment {
+    pub area: LiveArea,
+    pub started_at: DateTime<Local>,
+}
+
+/// 正在进行中的直播场次，开播时写入状态文件，下播后删除
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveSession {
+    pub room_id: i64,
+    pub live_key: String,
+    pub area: LiveArea,
+    pub started_at: DateTime<Local>,
+    /// 按时间顺序记录的各分区片段，最后一项为当前分区
+    #[serde(default)]
+    pub segments: Vec<AreaSegment>,
+}
+
+impl LiveSession {
+    pub fn new(room_id: i64, live_key: String, area: LiveArea) -> Self {
+        let started_at = Local::now();
+        Self {
+            room_id,
+            live_key,
+            segments: vec![AreaSegment { area: area.clone(), started_at }],
+            area,
+            started_at,
+        }
+    }
+
+    /// 读取状态文件，文件不存在时返回 `None`
+    pub fn load(path: impl AsRef<Path>) -> Result<Option<Self>> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let mut session: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        // 旧版本的状态文件没有分区片段，以开播时的分区补上
+        if session.segments.is_empty() {
+            session.segments.push(AreaSegment { area: session.area.clone(), started_at: session.started_at });
+        }
+        Ok(Some(session))
+    }
+
+    /// 写入状态文件
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// 删除状态文件，文件不存在时不报错
+    pub fn remove(path: impl AsRef<Path>) -> Result<()> {
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// 记录直播中切换到新的分区
+    pub fn switch_area(&mut self, area: LiveArea) {
+        self.segments.push(AreaSegment { area: area.clone(), started_at: Local::now() });
+        self.area = area;
+    }
+
+    /// 各分区片段及其持续时长，最后一个片段持续到当前时间
+    pub fn segment_durations(&self) -> Vec<(&AreaSegment, chrono::Duration)> {
+        let now = Local::now();
+        self.segments
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| {
+                let end = self.segments.get(i + 1).map_or(now, |next| next.started_at);
+                (segment, end - segment.started_at)
+            })
+            .collect()
+    }
+
+    /// 已直播的时长
+    pub fn elapsed(&self) -> chrono::Duration {
+        Local::now() - self.started_at
+    }
+}
@@ -0,0 +1,45 @@
+use bili_live::{BiliLiveError, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use crate::user_warning;
+
+/// 关闭直播的默认超时时间（秒）
+pub const DEFAULT_SHUTDOWN_TIMEOUT: u64 = 15;
+
+/// 关闭直播超时时的退出码，与 coreutils 的 timeout 一致
+pub const EXIT_TIMEOUT: i32 = 124;
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// 监听退出信号，返回的通道在收到第一个信号时收到通知
+///
+/// 覆盖 Ctrl+C、SIGTERM、SIGHUP 以及 Windows 下关闭控制台窗口，
+/// 关闭过程中再次收到的信号会被忽略，以免直播停在半途。
+pub fn listen() -> Result<mpsc::Receiver<()>> {
+    let (tx, rx) = mpsc::channel();
+    ctrlc::set_handler(move || {
+        if SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+            user_warning!("正在关闭直播，请稍候...");
+        } else {
+            let _ = tx.send(());
+        }
+    })
+    .map_err(|e| BiliLiveError::ShutdownError(e.to_string()))?;
+    Ok(rx)
+}
+
+/// 在限定时间内执行关闭操作，超时后放弃等待
+pub fn run_with_timeout<T, F>(timeout: Duration, f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(f());
+    });
+    rx.recv_timeout(timeout)
+        .unwrap_or(Err(BiliLiveError::ShutdownTimeout(timeout.as_secs())))
+}
@@ -0,0 +1,454 @@
+use bili_live::{AreaParent, BiliLiveError, BilibiliClient, LiveArea, LiveSession, Result, Rtmp, StopLiveData};
+use chrono::Local;
+use log::LevelFilter;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+use crate::{area_match, utils};
+
+/// 刷新界面与检查退出信号的间隔
+const TICK: Duration = Duration::from_millis(250);
+
+/// 直播统计的刷新间隔
+const STATS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 全屏界面需要同时占用终端的输入与输出
+pub fn check_terminal() -> Result<()> {
+    if std::io::stdin().is_terminal() && std::io::stdout().is_terminal() {
+        Ok(())
+    } else {
+        Err(BiliLiveError::InputError("--tui 需要在终端中运行".to_string()))
+    }
+}
+
+/// 进入全屏界面，期间关闭日志输出以免打乱画面，离开时恢复终端与日志级别
+struct Screen {
+    terminal: DefaultTerminal,
+    log_level: LevelFilter,
+}
+
+impl Screen {
+    fn enter() -> Result<Self> {
+        let terminal = ratatui::try_init()?;
+        let log_level = log::max_level();
+        log::set_max_level(LevelFilter::Off);
+        Ok(Self { terminal, log_level })
+    }
+
+    fn draw(&mut self, render: impl FnOnce(&mut Frame)) -> Result<()> {
+        self.terminal.draw(render)?;
+        Ok(())
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        ratatui::restore();
+        log::set_max_level(self.log_level);
+    }
+}
+
+/// 在 `TICK` 内等待一次按键，忽略按键释放等其他事件
+fn next_key() -> Result<Option<KeyEvent>> {
+    if !event::poll(TICK)? {
+        return Ok(None);
+    }
+    match event::read()? {
+        Event::Key(key) if key.kind == KeyEventKind::Press => Ok(Some(key)),
+        _ => Ok(None),
+    }
+}
+
+fn is_ctrl_c(key: &KeyEvent) -> bool {
+    key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c')
+}
+
+/// 全屏选择分区：输入名称或拼音首字母即时过滤，最近使用与收藏的分区排在最前
+pub fn pick_area(areas: &[AreaParent], recent: &[LiveArea], favorites: &[LiveArea]) -> Result<LiveArea> {
+    let all: Vec<_> = areas.iter().flat_map(|parent| parent.list.iter().map(move |area| (parent, area))).collect();
+    let picks = utils::quick_picks(recent, favorites);
+    let mut screen = Screen::enter()?;
+    let mut query = String::new();
+    let mut state = ListState::default().with_selected(Some(0));
+
+    loop {
+        let mut options = if query.is_empty() { all.clone() } else { area_match::rank(areas, &query) };
+        if query.is_empty() {
+            for (pick, _) in picks.iter().rev() {
+                if let Some(i) = options.iter().position(|(_, area)| area.id == pick.id) {
+                    let option = options.remove(i);
+                    options.insert(0, option);
+                }
+            }
+        }
+        if state.selected().is_none_or(|i| i >= options.len()) {
+            state.select(Some(0));
+        }
+
+        screen.draw(|frame| {
+            let [input, list, help] =
+                Layout::vertical([Constraint::Length(3), Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+            frame.render_widget(Paragraph::new(query.as_str()).block(Block::bordered().title(" 搜索分区 ")), input);
+            let items: Vec<ListItem> = options
+                .iter()
+                .map(|(parent, area)| {
+                    let mut line = Line::from(format!("{} / {} - {}", parent.name, area.name, area.id));
+                    if query.is_empty()
+                        && let Some((_, tag)) = picks.iter().find(|(pick, _)| pick.id == area.id)
+                    {
+                        line.push_span(Span::from(format!(" ({})", tag)).dim());
+                    }
+                    ListItem::new(line)
+                })
+                .collect();
+            let title = format!(" 分区 ({}) ", options.len());
+            let list_widget = List::new(items)
+                .block(Block::bordered().title(title))
+                .highlight_symbol("> ")
+                .highlight_style(Style::new().reversed());
+            frame.render_stateful_widget(list_widget, list, &mut state);
+            frame.render_widget(Line::from("↑/↓ 移动  Enter 选择  Esc 取消").dim(), help);
+        })?;
+
+        let Some(key) = next_key()? else { continue };
+        match key.code {
+            _ if is_ctrl_c(&key) => break,
+            KeyCode::Esc => break,
+            KeyCode::Up => state.select_previous(),
+            KeyCode::Down => state.select_next(),
+            KeyCode::Backspace => {
+                query.pop();
+                state.select(Some(0));
+            }
+            KeyCode::Char(c) => {
+                query.push(c);
+                state.select(Some(0));
+            }
+            KeyCode::Enter => {
+                if let Some((_, area)) = state.selected().and_then(|i| options.get(i)) {
+                    let area = LiveArea { id: area.id, name: Some(area.name.clone()) };
+                    drop(screen);
+                    crate::user_success!("已选择分区: {} (ID: {})", area.name.as_deref().unwrap_or_default(), area.id);
+                    return Ok(area);
+                }
+            }
+            _ => {}
+        }
+    }
+    Err(BiliLiveError::AreaSelectionError("已取消分区选择".to_string()))
+}
+
+/// 按终端显示宽度补齐的字段名，汉字占两列
+fn label(name: &str) -> Span<'static> {
+    let width: usize = name.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum();
+    Span::from(format!("{}{}", name, " ".repeat(10usize.saturating_sub(width))))
+}
+
+/// 后台线程获取的实时数据
+struct Snapshot {
+    online: Option<i64>,
+    stats: Option<StopLiveData>,
+    error: Option<String>,
+    /// 状态文件中记录的当前分区，通过 switch-area 切换后随之更新
+    area: Option<LiveArea>,
+    at: chrono::DateTime<Local>,
+}
+
+fn snapshot(client: &BilibiliClient, mid: i64, live_key: &str, session_file: &Path) -> Snapshot {
+    let room = client.room_info(mid);
+    let stats = client.live_stats(live_key);
+    let error = room.as_ref().err().or(stats.as_ref().err()).map(ToString::to_string);
+    let area = match LiveSession::load(session_file) {
+        Ok(Some(saved)) if saved.live_key == live_key => Some(saved.area),
+        _ => None,
+    };
+    Snapshot { online: room.ok().map(|r| r.online), stats: stats.ok(), error, area, at: Local::now() }
+}
+
+#[derive(Debug, PartialEq)]
+enum Mode {
+    Normal,
+    EditTitle(String),
+    ConfirmStop,
+}
+
+/// 按键触发的操作
+#[derive(Debug, PartialEq)]
+enum Action {
+    None,
+    Stop,
+    UpdateTitle(String),
+    Copy(String),
+}
+
+/// 直播状态面板
+struct LivePanel<'a> {
+    session: &'a LiveSession,
+    rtmp: Option<&'a Rtmp>,
+    title: String,
+    area: LiveArea,
+    reveal: bool,
+    mode: Mode,
+    snapshot: Option<Snapshot>,
+    message: String,
+}
+
+impl LivePanel<'_> {
+    fn render(&self, frame: &mut Frame) {
+        let [info, stats, help] =
+            Layout::vertical([Constraint::Length(9), Constraint::Min(6), Constraint::Length(3)]).areas(frame.area());
+
+        let seconds = self.session.elapsed().num_seconds().max(0);
+        let code = match self.rtmp {
+            Some(rtmp) if self.reveal => rtmp.code.clone(),
+            Some(rtmp) => utils::mask_rtmp_code(&rtmp.code),
+            None => "继续上次的直播，推流码未知".to_string(),
+        };
+        let area = &self.area;
+        let field = |name: &str, value: String| Line::from(vec![label(name).bold(), Span::from(value)]);
+        let lines = vec![
+            field("直播间", self.session.room_id.to_string()),
+            field("标题", self.title.clone()),
+            field("分区", format!("{} - {}", area.name.as_deref().unwrap_or("未知分区"), area.id)),
+            field("已直播", format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)),
+            field("RTMP", self.rtmp.map(|r| r.addr.clone()).unwrap_or_default()),
+            field("推流码", code),
+            field("场次", self.session.live_key.clone()),
+        ];
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" 🔴 直播中 ")), info);
+
+        let lines = match &self.snapshot {
+            None => vec![Line::from("正在获取直播统计...").dim()],
+            Some(snapshot) => {
+                let count = |value: Option<i64>| value.map_or("-".to_string(), |v| v.to_string());
+                let stats = snapshot.stats.as_ref();
+                let mut lines = vec![
+                    field("人气", count(snapshot.online)),
+                    field("弹幕数", count(stats.map(|s| s.danmu_num))),
+                    field("累计观看", count(stats.map(|s| s.watched_count))),
+                    field("新增粉丝", count(stats.map(|s| s.add_fans))),
+                    Line::from(format!("更新于 {}", snapshot.at.format("%H:%M:%S"))).dim(),
+                ];
+                if let Some(error) = &snapshot.error {
+                    lines.push(Line::from(format!("获取失败: {}", error)).red());
+                }
+                lines
+            }
+        };
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" 实时统计 ")), stats);
+
+        let keys = match &self.mode {
+            Mode::Normal => "s 下播  t 修改标题  c 复制推流码  r 显示/隐藏推流码".to_string(),
+            Mode::EditTitle(title) => format!("新标题: {}▏ (Enter 确认，Esc 取消)", title),
+            Mode::ConfirmStop => "确认下播？(y 确认，其他键取消)".to_string(),
+        };
+        let lines = vec![Line::from(keys), Line::from(self.message.as_str()).dim()];
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered()), help);
+    }
+
+    /// 处理一次按键，只修改面板状态，需要调用接口或访问剪贴板的操作交给调用方执行
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        match std::mem::replace(&mut self.mode, Mode::Normal) {
+            Mode::ConfirmStop if matches!(key.code, KeyCode::Char('y' | 'Y')) => return Action::Stop,
+            Mode::ConfirmStop => self.message = "已取消下播".to_string(),
+            Mode::EditTitle(mut title) => match key.code {
+                KeyCode::Esc => self.message = "已取消修改标题".to_string(),
+                KeyCode::Enter => {
+                    let title = title.trim();
+                    if title.is_empty() {
+                        self.message = "直播间标题不能为空".to_string();
+                    } else {
+                        return Action::UpdateTitle(title.to_string());
+                    }
+                }
+                KeyCode::Backspace => {
+                    title.pop();
+                    self.mode = Mode::EditTitle(title);
+                }
+                KeyCode::Char(c) => {
+                    title.push(c);
+                    self.mode = Mode::EditTitle(title);
+                }
+                _ => self.mode = Mode::EditTitle(title),
+            },
+            Mode::Normal => match key.code {
+                _ if is_ctrl_c(&key) => self.mode = Mode::ConfirmStop,
+                KeyCode::Char('s') => self.mode = Mode::ConfirmStop,
+                KeyCode::Char('t') => self.mode = Mode::EditTitle(self.title.clone()),
+                KeyCode::Char('r') => self.reveal = !self.reveal,
+                KeyCode::Char('c') => match self.rtmp {
+                    Some(rtmp) => return Action::Copy(rtmp.code.clone()),
+                    None => self.message = "没有可复制的推流码".to_string(),
+                },
+                _ => {}
+            },
+        }
+        Action::None
+    }
+
+    /// 执行按键对应的操作，返回是否需要下播
+    fn apply(&mut self, client: &BilibiliClient, action: Action) -> bool {
+        match action {
+            Action::None => {}
+            Action::Stop => return true,
+            Action::UpdateTitle(title) => match client.update_title(&title) {
+                Ok(()) => {
+                    self.message = format!("直播间标题已修改为: {}", title);
+                    self.title = title;
+                }
+                Err(e) => self.message = format!("修改标题失败: {}", e),
+            },
+            Action::Copy(code) => {
+                self.message = match utils::copy_to_clipboard(&code) {
+                    Ok(()) => "推流码已复制到剪贴板".to_string(),
+                    Err(e) => format!("复制到剪贴板失败: {}", e),
+                }
+            }
+        }
+        false
+    }
+}
+
+/// 显示直播状态面板，直到用户确认下播或收到退出信号
+pub fn run_live(
+    client: &BilibiliClient,
+    session: &LiveSession,
+    session_file: &Path,
+    rtmp: Option<&Rtmp>,
+    signals: &Receiver<()>,
+) -> Result<()> {
+    let mid = client.nav()?.mid;
+    let title = client.room_info(mid)?.title;
+    let mut screen = Screen::enter()?;
+    let mut panel = LivePanel {
+        session,
+        rtmp,
+        title,
+        area: session.area.clone(),
+        reveal: false,
+        mode: Mode::Normal,
+        snapshot: None,
+        message: String::new(),
+    };
+
+    // 统计线程不参与退出流程：获取统计可能阻塞到请求超时，不能因此推迟下播
+    let (tx, snapshots) = mpsc::channel();
+    let stats_client = client.clone();
+    let live_key = session.live_key.clone();
+    let session_file = session_file.to_path_buf();
+    std::thread::spawn(move || {
+        while tx.send(snapshot(&stats_client, mid, &live_key, &session_file)).is_ok() {
+            std::thread::sleep(STATS_INTERVAL);
+        }
+    });
+
+    loop {
+        if signals.try_recv().is_ok() {
+            return Ok(());
+        }
+        if let Some(mut snapshot) = snapshots.try_iter().last() {
+            if let Some(area) = snapshot.area.take() {
+                panel.area = area;
+            }
+            panel.snapshot = Some(snapshot);
+        }
+        screen.draw(|frame| panel.render(frame))?;
+        if let Some(key) = next_key()? {
+            let action = panel.handle_key(key);
+            if panel.apply(client, action) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(panel: &mut LivePanel, code: KeyCode) -> Action {
+        panel.handle_key(KeyEvent::from(code))
+    }
+
+    fn panel<'a>(session: &'a LiveSession, rtmp: Option<&'a Rtmp>) -> LivePanel<'a> {
+        LivePanel {
+            session,
+            rtmp,
+            title: "旧标题".to_string(),
+            area: session.area.clone(),
+            reveal: false,
+            mode: Mode::Normal,
+            snapshot: None,
+            message: String::new(),
+        }
+    }
+
+    fn session() -> LiveSession {
+        LiveSession::new(1, "key".to_string(), LiveArea { id: 235, name: None })
+    }
+
+    #[test]
+    fn stop_needs_confirmation() {
+        let session = session();
+        let mut panel = panel(&session, None);
+
+        assert_eq!(press(&mut panel, KeyCode::Char('s')), Action::None);
+        assert_eq!(panel.mode, Mode::ConfirmStop);
+        assert_eq!(press(&mut panel, KeyCode::Char('n')), Action::None);
+        assert_eq!(panel.mode, Mode::Normal);
+
+        press(&mut panel, KeyCode::Char('s'));
+        assert_eq!(press(&mut panel, KeyCode::Char('y')), Action::Stop);
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(panel.handle_key(ctrl_c), Action::None);
+        assert_eq!(panel.mode, Mode::ConfirmStop);
+    }
+
+    #[test]
+    fn title_is_edited_in_place() {
+        let session = session();
+        let mut panel = panel(&session, None);
+
+        press(&mut panel, KeyCode::Char('t'));
+        assert_eq!(panel.mode, Mode::EditTitle("旧标题".to_string()));
+        press(&mut panel, KeyCode::Backspace);
+        press(&mut panel, KeyCode::Char('s'));
+        assert_eq!(press(&mut panel, KeyCode::Enter), Action::UpdateTitle("旧标s".to_string()));
+        assert_eq!(panel.mode, Mode::Normal);
+
+        press(&mut panel, KeyCode::Char('t'));
+        press(&mut panel, KeyCode::Esc);
+        assert_eq!(panel.message, "已取消修改标题");
+
+        press(&mut panel, KeyCode::Char('t'));
+        for _ in 0..3 {
+            press(&mut panel, KeyCode::Backspace);
+        }
+        assert_eq!(press(&mut panel, KeyCode::Enter), Action::None);
+        assert_eq!(panel.message, "直播间标题不能为空");
+    }
+
+    #[test]
+    fn code_can_be_revealed_and_copied() {
+        let session = session();
+        let rtmp = Rtmp { addr: "rtmp://example/".to_string(), code: "?streamname=abc".to_string() };
+        let mut panel = panel(&session, Some(&rtmp));
+
+        press(&mut panel, KeyCode::Char('r'));
+        assert!(panel.reveal);
+        press(&mut panel, KeyCode::Char('r'));
+        assert!(!panel.reveal);
+        assert_eq!(press(&mut panel, KeyCode::Char('c')), Action::Copy(rtmp.code.clone()));
+
+        let mut panel = self::panel(&session, None);
+        assert_eq!(press(&mut panel, KeyCode::Char('c')), Action::None);
+        assert_eq!(panel.message, "没有可复制的推流码");
+    }
+}
@@ -0,0 +1,248 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::error::{BiliLiveError, Result};
+
+/// B站接口统一的响应外壳，`data` 先保持原样，确认 `code` 后再转换为具体类型
+#[derive(Debug, Deserialize)]
+pub(crate) struct ApiResponse {
+    pub code: i64,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub msg: String,
+    #[serde(default)]
+    pub data: serde_json::Value,
+}
+
+impl ApiResponse {
+    pub fn into_data<T: for<'de> Deserialize<'de>>(self) -> Result<T> {
+        if self.code != 0 {
+            let message = if !self.message.is_empty() {
+                self.message
+            } else if !self.msg.is_empty() {
+                self.msg
+            } else {
+                "未知错误".to_string()
+            };
+            return Err(BiliLiveError::ApiError(format!("{} (code: {})", message, self.code)));
+        }
+        Ok(serde_json::from_value(self.data)?)
+    }
+}
+
+/// 分区ID在部分接口中是字符串，在另一些接口中是数字
+fn de_u32<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<u32, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumOrStr {
+        Num(u32),
+        Str(String),
+    }
+
+    match NumOrStr::deserialize(deserializer)? {
+        NumOrStr::Num(n) => Ok(n),
+        NumOrStr::Str(s) => s.trim().parse().map_err(serde::de::Error::custom),
+    }
+}
+
+pub struct QRStatus {
+    pub waiting: i32,
+    pub scanned: i32,
+    pub success: i32,
+}
+
+pub const QR_STATUS: QRStatus = QRStatus {
+    waiting: 86101, // 等待扫码
+    scanned: 86090, // 已扫码，等待确认
+    success: 0,     // 登录成功
+};
+
+/// 登录二维码
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QrCodeData {
+    pub url: String,
+    pub qrcode_key: String,
+}
+
+/// 二维码扫描状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QrPollData {
+    pub url: String,
+    pub code: i32,
+    pub message: String,
+    /// 刷新登录凭据时使用，仅登录成功时有值
+    #[serde(default)]
+    pub refresh_token: String,
+    /// 响应中的 Set-Cookie 响应头
+    #[serde(skip)]
+    pub set_cookies: Vec<String>,
+}
+
+/// 设备标识
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpiData {
+    pub b_3: String,
+    pub b_4: String,
+}
+
+/// 登录凭据是否需要刷新
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CookieInfoData {
+    pub refresh: bool,
+    /// 服务端当前的毫秒时间戳
+    #[serde(default)]
+    pub timestamp: i64,
+}
+
+/// 刷新登录凭据接口返回的数据，新的 SESSDATA 等通过 Set-Cookie 返回
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshData {
+    #[serde(default)]
+    pub message: String,
+    pub refresh_token: String,
+}
+
+/// 当前登录用户信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NavData {
+    pub mid: i64,
+    #[serde(default)]
+    pub uname: String,
+}
+
+/// 用户的直播间信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomInfoData {
+    pub roomid: i64,
+    /// 直播状态，1 为直播中
+    #[serde(rename(deserialize = "liveStatus"), default)]
+    pub live_status: i32,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub url: String,
+    /// 直播间人气
+    #[serde(default)]
+    pub online: i64,
+}
+
+impl RoomInfoData {
+    /// 直播间是否正在直播
+    pub fn is_live(&self) -> bool {
+        self.live_status == 1
+    }
+}
+
+/// 直播间的详细信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomDetailData {
+    pub room_id: i64,
+    #[serde(default)]
+    pub title: String,
+    /// 直播间简介
+    #[serde(default)]
+    pub description: String,
+    /// 以逗号分隔的直播间标签
+    #[serde(default)]
+    pub tags: String,
+}
+
+impl RoomDetailData {
+    /// 拆分后的标签列表
+    pub fn tag_list(&self) -> Vec<String> {
+        self.tags
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// 直播间公告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomNewsData {
+    #[serde(default)]
+    pub content: String,
+}
+
+/// 图片上传接口返回的数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadImageData {
+    /// 上传后的图片地址
+    pub location: String,
+}
+
+/// 一级分区
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AreaParent {
+    pub name: String,
+    #[serde(default)]
+    pub list: Vec<Area>,
+}
+
+/// 二级分区
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Area {
+    #[serde(deserialize_with = "de_u32")]
+    pub id: u32,
+    pub name: String,
+}
+
+/// 按 ETag 条件获取分区列表的结果
+#[derive(Debug, Clone)]
+pub enum AreaListFetch {
+    /// 服务端确认分区列表没有变化
+    NotModified,
+    /// 新的分区列表及其 ETag，服务端未返回 ETag 时为空
+    Updated { areas: Vec<AreaParent>, etag: Option<String> },
+}
+
+/// 最近使用过的直播分区
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentArea {
+    #[serde(deserialize_with = "de_u32")]
+    pub id: u32,
+    pub name: String,
+}
+
+/// 开播时选定的分区，名称未知时为空
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveArea {
+    pub id: u32,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// 推流地址与推流码
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rtmp {
+    pub addr: String,
+    pub code: String,
+}
+
+/// 开播接口返回的数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartLiveData {
+    pub rtmp: Rtmp,
+    pub live_key: String,
+}
+
+/// 下播后的直播统计信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StopLiveData {
+    #[serde(rename(deserialize = "AddFans"), default)]
+    pub add_fans: i64,
+    #[serde(rename(deserialize = "DanmuNum"), default)]
+    pub danmu_num: i64,
+    #[serde(rename(deserialize = "HamsterRmb"), default)]
+    pub hamster_rmb: i64,
+    #[serde(rename(deserialize = "LiveTime"), default)]
+    pub live_time: i64,
+    #[serde(rename(deserialize = "MaxOnline"), default)]
+    pub max_online: i64,
+    #[serde(rename(deserialize = "NewFansClub"), default)]
+    pub new_fans_club: i64,
+    #[serde(rename(deserialize = "WatchedCount"), default)]
+    pub watched_count: i64,
+}
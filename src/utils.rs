@@ -1,89 +1,26 @@
-use serde::{Deserialize, Serialize};
-use std::fs;
-use crate::error::{BiliLiveError, Result};
-use crate::{user_info, user_success, user_warning, user_input_prompt};
+use std::path::Path;
+use bili_live::{Area, AreaParent, BiliLiveError, BilibiliClient, LiveArea, Result, StartLiveData, StopLiveData, QR_STATUS};
+use crate::area_match;
+use crate::credentials::CredentialStore;
+use crate::{user_info, user_prompt, user_success, user_warning, user_input_prompt};
 use copypasta::{ClipboardContext, ClipboardProvider};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Cookies {
-    pub room_id: i32,
-    pub sessdata: String,
-    pub csrf_token: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct QRKeyResponseData {
-    url: String,
-    qrcode_key: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct QRKeyResponse {
-    data: QRKeyResponseData,
-}
-
-#[derive(Debug, Deserialize)]
-struct QrPollResponseData {
-    url: String,
-    code: i32,
-    message: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct QRPollResponse {
-    data: QrPollResponseData,
-}
-
-#[derive(Debug, Deserialize)]
-struct NavResponse {
-    data: NavData,
-}
-
-#[derive(Debug, Deserialize)]
-struct NavData {
-    mid: i64,
-}
-
-#[derive(Debug, Deserialize)]
-struct RoomInfoResponse {
-    data: RoomInfoData,
-}
-
-#[derive(Debug, Deserialize)]
-struct RoomInfoData {
-    roomid: i64,
-}
-
-const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/130.0.0.0 Safari/537.36 Edg/130.0.0.0";
-
-pub struct QRStatus {
-    pub waiting: i32,
-    pub scanned: i32,
-    pub success: i32,
-}
-
-pub const QR_STATUS: QRStatus = QRStatus {
-    waiting: 86101, // 等待扫码
-    scanned: 86090, // 已扫码，等待确认
-    success: 0,     // 登录成功
-};
-
 /// 复制文本到剪贴板
-fn copy_to_clipboard(text: &str) -> Result<()> {
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
     match ClipboardContext::new() {
         Ok(mut ctx) => {
             ctx.set_contents(text.to_owned())
-                .map_err(|e| BiliLiveError::IoError(std::io::Error::new(std::io::ErrorKind::Other, format!("复制到剪贴板失败: {}", e))))?;
+                .map_err(|e| BiliLiveError::IoError(std::io::Error::other(format!("复制到剪贴板失败: {}", e))))?;
             Ok(())
         }
         Err(e) => {
-            Err(BiliLiveError::IoError(std::io::Error::new(std::io::ErrorKind::Other, format!("无法访问剪贴板: {}", e))))
+            Err(BiliLiveError::IoError(std::io::Error::other(format!("无法访问剪贴板: {}", e))))
         }
     }
 }
 
 /// 打码显示推流码（只显示前6位和后4位）
-fn mask_rtmp_code(code: &str) -> String {
+pub fn mask_rtmp_code(code: &str) -> String {
     if code.len() <= 10 {
         return code.to_string();
     }
@@ -93,191 +30,179 @@ fn mask_rtmp_code(code: &str) -> String {
     format!("{}{}...{}", prefix, "*".repeat(masked_length.min(12)), suffix)
 }
 
-fn generate_qr_code() -> Result<QRKeyResponseData> {
-    let response = minreq::get("https://passport.bilibili.com/x/passport-login/web/qrcode/generate")
-        .with_header("User-Agent", DEFAULT_USER_AGENT)
-        .send()?;
-  
-    let response_text = response.as_str()?;
-    let qr_response: QRKeyResponse = serde_json::from_str(response_text)?;
-    Ok(qr_response.data)
-}
-
-fn poll_qr_status(
-    qrcode_key: &str,
-) -> Result<QrPollResponseData> {
-    let url = format!("https://passport.bilibili.com/x/passport-login/web/qrcode/poll?qrcode_key={}", qrcode_key);
-    let response = minreq::get(&url)
-        .with_header("User-Agent", DEFAULT_USER_AGENT)
-        .send()?;
-  
-    let response_text = response.as_str()?;
-    let poll_response: QRPollResponse = serde_json::from_str(response_text)?;
-    Ok(poll_response.data)
-}
-
-pub fn get_query_string(name: &str, url: &str) -> String {
-    let pairs: Vec<&str> = url.split('?').nth(1).unwrap_or("").split('&').collect();
-
-    for pair in pairs {
-        let mut parts = pair.split('=');
-        if let Some(key) = parts.next() {
-            if key == name {
-                return parts.next().unwrap_or("").to_string();
-            }
-        }
+/// 读取一行用户输入并去除首尾空白，标准输入已关闭时返回错误
+pub fn read_line() -> Result<String> {
+    let mut input = String::new();
+    let read = std::io::stdin()
+        .read_line(&mut input)
+        .map_err(|e| BiliLiveError::InputError(format!("读取用户输入失败: {}", e)))?;
+    if read == 0 {
+        return Err(BiliLiveError::InputError("标准输入已关闭，无法继续交互".to_string()));
     }
-    String::new()
-}
-
-pub fn get_roomid(sessdata: &str) -> Result<i32> {
-    let response = minreq::get("https://api.bilibili.com/x/web-interface/nav")
-        .with_header("User-Agent", DEFAULT_USER_AGENT)
-        .with_header("Cookie", &format!("SESSDATA={}", sessdata))
-        .send()?;
-  
-    let response_text = response.as_str()?;
-    let nav_response: NavResponse = serde_json::from_str(response_text)?;
-    let user_code = nav_response.data.mid.to_string();
-
-    let url = format!("https://api.live.bilibili.com/room/v1/Room/getRoomInfoOld?mid={}", user_code);
-    let response = minreq::get(&url)
-        .with_header("User-Agent", DEFAULT_USER_AGENT)
-        .send()?;
-  
-    let response_text = response.as_str()?;
-    let room_info: RoomInfoResponse = serde_json::from_str(response_text)?;
-    Ok(room_info.data.roomid as i32)
-}
-
-pub fn save_cookies(set_cookies_url: &str) -> Result<()> {
-    let bili_sessdata = get_query_string("SESSDATA", set_cookies_url);
-    let csrf = get_query_string("bili_jct", set_cookies_url);
-    let cookies = Cookies {
-        room_id: get_roomid(&bili_sessdata)?,
-        sessdata: bili_sessdata,
-        csrf_token: csrf,
-    };
-
-    let cookies_json = serde_json::to_string_pretty(&cookies)?;
-    fs::write("cookies.json", cookies_json)?;
-    user_success!("Cookies保存成功");
-    Ok(())
+    Ok(input.trim().to_string())
 }
 
-pub fn read_cookies() -> Result<Cookies> {
-    let cookies_str = std::fs::read_to_string("./cookies.json")
-        .map_err(|e| BiliLiveError::IoError(e))?;
-    let cookies: Cookies = serde_json::from_str(&cookies_str)
-        .map_err(|e| BiliLiveError::JsonError(e))?;
-    Ok(cookies)
-}
-
-pub fn check_status() -> Result<bool> {
+/// 检查本地凭据是否有效，有效时将其加载到客户端中
+pub fn check_status(client: &mut BilibiliClient, store: &CredentialStore, cookies_file: &Path) -> Result<bool> {
     user_info!("检查登录状态...");
     // 先检查一下文件是否存在
-    if !std::path::Path::new("cookies.json").exists() {
+    if !cookies_file.exists() {
         user_warning!("cookies.json文件不存在");
         return Ok(false);
     }
     // 检查一下文件内容是否为空
-    if std::fs::read_to_string("cookies.json")
-        .map_err(|e| BiliLiveError::IoError(e))?
-        .is_empty() {
+    if std::fs::read_to_string(cookies_file)?.is_empty() {
         user_warning!("cookies.json文件为空");
         return Ok(false);
     }
-    // 读取cookies.json文件
-    let sessdata = read_cookies()?.sessdata;
-    // 发送请求
-    let response = minreq::get("https://api.bilibili.com/x/web-interface/nav")
-        .with_header("User-Agent", DEFAULT_USER_AGENT)
-        .with_header("Cookie", &format!("SESSDATA={}", sessdata))
-        .send()?;
-  
-    // 解析响应
-    let response_text = response.as_str()?;
-    let response_json: serde_json::Value = serde_json::from_str(response_text)?;
-    let code = response_json["code"].as_i64()
-        .ok_or_else(|| BiliLiveError::ParseError("无法解析响应码".to_string()))?;
-    if code == 0 {
+    client.set_cookies(store.load(cookies_file)?);
+    if client.is_logged_in()? {
+        // 服务端提示需要刷新时提前刷新，失败不影响继续使用当前凭据
+        match client.cookie_info() {
+            Ok(info) if info.refresh => {
+                user_info!("登录凭据即将过期，尝试刷新...");
+                if let Err(e) = refresh_cookies(client, store, cookies_file) {
+                    user_warning!("刷新登录凭据失败: {}，继续使用当前凭据", e);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => user_warning!("检查登录凭据是否需要刷新失败: {}", e),
+        }
         return Ok(true);
-    } else {
-        user_warning!("登录状态异常");
+    }
+
+    user_warning!("登录状态异常");
+    if client.cookies()?.refresh_token.is_empty() {
         return Ok(false);
     }
+    user_info!("尝试使用 refresh_token 刷新登录凭据...");
+    match refresh_cookies(client, store, cookies_file) {
+        Ok(()) => client.is_logged_in(),
+        Err(e) => {
+            user_warning!("刷新登录凭据失败: {}", e);
+            Ok(false)
+        }
+    }
+}
+
+/// 刷新登录凭据并保存
+fn refresh_cookies(client: &mut BilibiliClient, store: &CredentialStore, cookies_file: &Path) -> Result<()> {
+    let old_refresh_token = client.cookies()?.refresh_token.clone();
+    let cookies = client.refresh_cookies()?;
+    // 刷新后服务端已更换凭据，先保存新凭据，确认失败也不能丢失
+    store.save(&cookies, cookies_file)?;
+    user_success!("登录凭据已刷新");
+    if let Err(e) = client.confirm_refresh(&old_refresh_token) {
+        user_warning!("确认刷新登录凭据失败: {}，新凭据已保存", e);
+    }
+    Ok(())
+}
+
+/// 分区选择菜单中的一次输入
+enum PickerInput {
+    Number(usize),
+    /// `b`：返回上一级
+    Back,
+    /// `q`：放弃选择
+    Quit,
+    Text(String),
+}
+
+/// 读取分区菜单的输入，空行视为无效输入，标准输入关闭时视为取消选择
+fn read_picker_input(prompt: &str) -> Result<PickerInput> {
+    user_input_prompt!("{}", prompt);
+    let mut input = String::new();
+    match std::io::stdin().read_line(&mut input) {
+        Ok(0) => return Err(BiliLiveError::AreaSelectionError("标准输入已关闭，已取消分区选择".to_string())),
+        Err(e) => return Err(BiliLiveError::AreaSelectionError(format!("读取用户输入失败: {}", e))),
+        Ok(_) => {}
+    }
+    let input = input.trim();
+    Ok(match input.to_lowercase().as_str() {
+        "q" => PickerInput::Quit,
+        "b" => PickerInput::Back,
+        _ => match input.parse() {
+            Ok(number) => PickerInput::Number(number),
+            Err(_) => PickerInput::Text(input.to_string()),
+        },
+    })
+}
+
+fn selection_cancelled() -> BiliLiveError {
+    BiliLiveError::AreaSelectionError("已取消分区选择".to_string())
 }
 
-pub fn get_area_choice() -> Result<u32> {
-    let response = minreq::get("https://api.live.bilibili.com/room/v1/Area/getList")
-        .with_header("User-Agent", DEFAULT_USER_AGENT)
-        .send()?;
-  
-    let response_text = response.as_str()?;
-    let area_list: serde_json::Value = serde_json::from_str(response_text)?;
-  
+pub fn get_area_choice(area_list: &[AreaParent]) -> Result<LiveArea> {
     loop {
         // 显示一级分区
         user_info!("一级分区列表:");
-        if let Some(data) = area_list["data"].as_array() {
-            for (i, area) in data.iter().enumerate() {
-                user_info!("{}. {}", i+1, area["name"]);
-            }
+        for (i, area) in area_list.iter().enumerate() {
+            user_info!("{}. {}", i+1, area.name);
         }
-      
-        user_input_prompt!("请输入一级分区编号: ");
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input)?;
-        let first_choice: usize = input.trim().parse()?;
-      
-        if first_choice == 0 {
-            user_warning!("你是抱着多大的觉悟在一级菜单按下0的？");
-            continue;
-        }
-      
-        if let Some(data) = area_list["data"].as_array() {
-            if first_choice > 0 && first_choice <= data.len() {
-                let selected_first_area = &data[first_choice-1];
-              
-                // 显示二级分区
-                if let Some(second_list) = selected_first_area["list"].as_array() {
-                    loop {
-                        user_info!("二级分区列表 ({}):", selected_first_area["name"]);
-                        for (i, area) in second_list.iter().enumerate() {
-                            user_info!("{}. {} - {}", i+1, area["name"], area["id"]);
-                        }
-                      
-                        user_input_prompt!("请输入二级分区编号(输入0返回): ");
-                        let mut second_input = String::new();
-                        std::io::stdin().read_line(&mut second_input)?;
-                        let second_choice: usize = second_input.trim().parse()?;
-                      
-                        if second_choice == 0 {
-                            break;
-                        }
-                      
-                        if second_choice > 0 && second_choice <= second_list.len() {
-                            let selected_area = &second_list[second_choice-1];
-                            user_success!("已选择分区: {} (ID: {})", selected_area["name"], selected_area["id"]);
-                            let id_str = selected_area["id"].as_str().unwrap_or("");
-                            let numeric_id: String = id_str.chars().filter(|c| c.is_numeric()).collect();
-                            return Ok(numeric_id.parse::<u32>()?);
-                        }
-                      
-                        user_warning!("无效的选择，请重新输入");
+
+        match read_picker_input("请输入一级分区编号，或输入名称、拼音首字母搜索(输入q退出): ")? {
+            PickerInput::Quit => return Err(selection_cancelled()),
+            PickerInput::Back => user_warning!("已经在一级分区列表，输入q退出"),
+            PickerInput::Number(0) => user_warning!("你是抱着多大的觉悟在一级菜单按下0的？"),
+            PickerInput::Number(first_choice) => match area_list.get(first_choice-1) {
+                Some(selected_first_area) => {
+                    // 显示二级分区
+                    let options: Vec<_> = selected_first_area.list.iter().map(|area| (selected_first_area, area)).collect();
+                    let title = format!("二级分区列表 ({}):", selected_first_area.name);
+                    if let Some(area) = choose_area_from(&title, &options, false)? {
+                        return Ok(area);
                     }
                 }
+                None => user_warning!("无效的选择，请重新输入"),
+            },
+            PickerInput::Text(query) if query.is_empty() => user_warning!("无效的选择，请重新输入"),
+            PickerInput::Text(query) => {
+                let matches = area_match::rank(area_list, &query);
+                if matches.is_empty() {
+                    user_warning!("没有找到与 {} 匹配的分区，请从列表中选择", query);
+                    continue;
+                }
+                let options = &matches[..matches.len().min(MAX_SEARCH_RESULTS)];
+                if let Some(area) = choose_area_from(&format!("搜索结果 ({}):", query), options, true)? {
+                    return Ok(area);
+                }
             }
         }
-      
-        user_warning!("无效的选择，请重新输入");
     }
 }
 
-pub fn start_login() -> Result<()> {
+/// 搜索分区时最多列出的结果数
+const MAX_SEARCH_RESULTS: usize = 10;
+
+/// 从二级分区列表或搜索结果中选择，返回上一级时为 `None`
+fn choose_area_from(title: &str, options: &[(&AreaParent, &Area)], show_parent: bool) -> Result<Option<LiveArea>> {
+    loop {
+        user_info!("{}", title);
+        for (i, (parent, area)) in options.iter().enumerate() {
+            if show_parent {
+                user_info!("{}. {} ({}) - {}", i+1, area.name, parent.name, area.id);
+            } else {
+                user_info!("{}. {} - {}", i+1, area.name, area.id);
+            }
+        }
+
+        match read_picker_input("请输入二级分区编号(输入0或b返回，q退出): ")? {
+            PickerInput::Quit => return Err(selection_cancelled()),
+            PickerInput::Back | PickerInput::Number(0) => return Ok(None),
+            PickerInput::Number(choice) if choice <= options.len() => {
+                let area = options[choice-1].1;
+                user_success!("已选择分区: {} (ID: {})", area.name, area.id);
+                return Ok(Some(LiveArea { id: area.id, name: Some(area.name.clone()) }));
+            }
+            PickerInput::Number(_) | PickerInput::Text(_) => user_warning!("无效的选择，请重新输入"),
+        }
+    }
+}
+
+pub fn start_login(client: &mut BilibiliClient, store: &CredentialStore, cookies_file: &Path) -> Result<()> {
     user_info!("开始B站二维码登录流程...");
 
-    let qr_data = generate_qr_code()?;
+    let qr_data = client.generate_qr_code()?;
     user_info!("请使用B站手机客户端如下链接：{}", qr_data.url);
 
     user_info!("或使用B站手机客户端扫描如下二维码");
@@ -292,7 +217,7 @@ pub fn start_login() -> Result<()> {
 
     // 轮询扫码状态
     loop {
-        let poll_data = poll_qr_status(&qr_data.qrcode_key)?;
+        let poll_data = client.poll_qr_code(&qr_data.qrcode_key)?;
 
         match poll_data.code {
             code if code == QR_STATUS.waiting => {
@@ -303,13 +228,15 @@ pub fn start_login() -> Result<()> {
             }
             code if code == QR_STATUS.success => {
                 user_success!("登录成功！");
-                save_cookies(&poll_data.url)?;
+                let cookies = client.login_with_poll(&poll_data)?;
+                store.save(&cookies, cookies_file)?;
+                user_success!("Cookies保存成功");
                 std::fs::remove_file("qrcode.png")?;
                 break;
             }
             _ => {
-                user_warning!("未知状态：{}", poll_data.message);
-                break;
+                let _ = std::fs::remove_file("qrcode.png");
+                return Err(BiliLiveError::LoginError(poll_data.message));
             }
         }
         std::thread::sleep(std::time::Duration::from_secs(2));
@@ -322,21 +249,19 @@ pub fn start_login() -> Result<()> {
 fn generate_and_save_qrcode(url: &str, filename: &str) -> Result<()> {
     use qrcode::QrCode;
     use image::Luma;
-    use std::path::Path;
 
     // 生成二维码
     let code = QrCode::new(url.as_bytes())?;
-  
+
     // 转换为图像
     let image = code.render::<Luma<u8>>()
         .quiet_zone(false)  // 禁用静区（可选）
         .min_dimensions(200, 200)  // 最小尺寸
         .build();
-  
+
     // 保存为PNG文件
-    let path = Path::new(filename);
-    image.save(path)?;
-  
+    image.save(Path::new(filename))?;
+
     Ok(())
 }
 
@@ -345,149 +270,125 @@ fn print_qrcode_in_terminal(url: &str) -> Result<()> {
     use std::io::Write;
 
     let code = QrCode::new(url.as_bytes())?;
-  
+
     // 转换为ASCII字符串
     let string = code.render()
         .light_color("  ")  // 浅色部分用空格
         .dark_color("██")  // 深色部分用方块
         .quiet_zone(false)
         .build();
-  
-    // 直接打印二维码，不使用日志宏，避免格式干扰
-    println!("{}", string);
-    std::io::stdout().flush()?;
+
+    // 直接打印二维码，不使用日志宏，避免格式干扰；与提示信息一样写到stderr，不占用stdout
+    eprintln!("{}", string);
+    std::io::stderr().flush()?;
     Ok(())
 }
 
 // 获取用户最近直播过的分区信息
-pub fn get_recent_live() -> Result<(String, String)> {
-    let room_id = read_cookies()?.room_id;
-    let url = format!("https://api.live.bilibili.com/room/v1/Area/getMyChooseArea?roomid={}", room_id);
-    let response = minreq::get(&url)
-        .with_header("User-Agent", DEFAULT_USER_AGENT)
-        .send()?;
-  
-    let response_text = response.as_str()?;
-    let json: serde_json::Value = serde_json::from_str(response_text)?;
-    let data = &json["data"][0];
-    let id = data["id"].as_str()
-        .ok_or_else(|| BiliLiveError::ParseError("无法解析分区ID".to_string()))?
-        .to_string();
-    let name = data["name"].as_str()
-        .ok_or_else(|| BiliLiveError::ParseError("无法解析分区名称".to_string()))?
-        .to_string();
-    Ok((id, name))
+pub fn get_recent_live(client: &BilibiliClient) -> Result<LiveArea> {
+    get_recent_areas(client)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| BiliLiveError::AreaSelectionError("没有最近直播过的分区，请使用 --area 指定分区".to_string()))
 }
 
-// 开始直播，获取推流码和推流地址
-pub fn start_live(area_id: &str, show_full_code: bool) -> Result<u64> {
-    let cookies = read_cookies()?;
-  
-    // 构建表单数据
-    let form_data = format!(
-        "room_id={}&area_v2={}&csrf={}&platform=pc_link",
-        cookies.room_id,
-        area_id,
-        cookies.csrf_token
-    );
-  
-    let response = minreq::post("https://api.live.bilibili.com/room/v1/Room/startLive")
-        .with_header("User-Agent", DEFAULT_USER_AGENT)
-        .with_header("Content-Type", "application/x-www-form-urlencoded")
-        .with_header("Cookie", &format!("SESSDATA={}", cookies.sessdata))
-        .with_header("platform", "web_electron_link")
-        .with_body(form_data)
-        .send()?;
-
-    let response_text = response.as_str()?;
-    let res: serde_json::Value = serde_json::from_str(response_text)?;
-
-    if res["code"].as_i64() != Some(0) {
-        return Err(BiliLiveError::ApiError(format!("API返回错误: {}", res["message"].as_str().unwrap_or("未知错误"))));
+/// 获取直播间最近使用过的全部分区，最近一次的在最前
+pub fn get_recent_areas(client: &BilibiliClient) -> Result<Vec<LiveArea>> {
+    Ok(client
+        .recent_areas()?
+        .into_iter()
+        .map(|area| LiveArea { id: area.id, name: Some(area.name) })
+        .collect())
+}
+
+/// 合并最近使用与收藏的分区并去重，最近使用的在前，附带来源标记
+pub fn quick_picks<'a>(recent: &'a [LiveArea], favorites: &'a [LiveArea]) -> Vec<(&'a LiveArea, &'static str)> {
+    let mut picks: Vec<(&LiveArea, &str)> = recent.iter().map(|area| (area, "最近")).collect();
+    for favorite in favorites {
+        match picks.iter_mut().find(|(area, _)| area.id == favorite.id) {
+            Some(pick) => pick.1 = "最近、收藏",
+            None => picks.push((favorite, "收藏")),
+        }
     }
+    picks
+}
+
+/// 列出最近使用与收藏的分区供快速选择，返回 `None` 时进入完整的分区菜单
+pub fn choose_quick_pick(recent: &[LiveArea], favorites: &[LiveArea]) -> Result<Option<LiveArea>> {
+    let picks = quick_picks(recent, favorites);
+    if picks.is_empty() {
+        return Ok(None);
+    }
+
+    loop {
+        user_info!("常用分区:");
+        for (i, (area, tag)) in picks.iter().enumerate() {
+            user_info!("{}. {} - {} ({})", i+1, area.name.as_deref().unwrap_or("未知分区"), area.id, tag);
+        }
+        user_prompt!("请输入编号选择分区(直接回车或输入y使用第1项，输入n选择其他分区，输入q退出): ");
+        let input = read_line()?.to_lowercase();
+        let choice: usize = match input.as_str() {
+            "" | "y" => 1,
+            "n" => return Ok(None),
+            "q" => return Err(BiliLiveError::AreaSelectionError("已取消分区选择".to_string())),
+            input => input.parse().unwrap_or(0),
+        };
+        if let Some((area, _)) = choice.checked_sub(1).and_then(|i| picks.get(i)) {
+            user_success!("使用分区: {} - {}", area.name.as_deref().unwrap_or("未知分区"), area.id);
+            return Ok(Some((*area).clone()));
+        }
+        user_warning!("无效的选择，请重新输入");
+    }
+}
+
+// 开始直播，获取推流码和推流地址
+pub fn start_live(client: &BilibiliClient, area_id: u32, show_full_code: bool, copy_code: bool) -> Result<StartLiveData> {
+    let data = client.start_live(area_id)?;
+    let rtmp_code = &data.rtmp.code;
+
+    user_success!("RTMP地址: {}", data.rtmp.addr);
 
-    let rtmp_addr = res["data"]["rtmp"]["addr"].as_str()
-        .ok_or_else(|| BiliLiveError::ParseError("缺少rtmp地址".to_string()))?;
-    let rtmp_code = res["data"]["rtmp"]["code"].as_str()
-        .ok_or_else(|| BiliLiveError::ParseError("缺少rtmp code".to_string()))?;
-    let live_key = res["data"]["live_key"].as_str()
-        .ok_or_else(|| BiliLiveError::ParseError("缺少live_key".to_string()))?;
-  
-    user_success!("RTMP地址: {}", rtmp_addr);
-    
     // 根据参数决定是否打码显示推流码
     if show_full_code {
         user_success!("推流码: {}", rtmp_code);
     } else {
         user_success!("推流码: {}", mask_rtmp_code(rtmp_code));
     }
-    
+
     // 自动复制推流码到剪贴板
-    match copy_to_clipboard(rtmp_code) {
-        Ok(()) => user_success!("推流码已自动复制到剪贴板！"),
-        Err(e) => user_warning!("复制到剪贴板失败: {}，请手动复制", e),
+    if copy_code {
+        match copy_to_clipboard(rtmp_code) {
+            Ok(()) => user_success!("推流码已自动复制到剪贴板！"),
+            Err(e) => user_warning!("复制到剪贴板失败: {}，请手动复制", e),
+        }
     }
 
-    Ok(live_key.parse::<u64>()?)
+    Ok(data)
 }
 
-pub fn stop_live(live_id: u64) -> Result<()> {
-    let cookies = read_cookies()?;
-  
-    // 构建表单数据
-    let form_data = format!(
-        "room_id={}&csrf={}&platform=web_electron_link",
-        cookies.room_id,
-        cookies.csrf_token
-    );
-  
-    let response = minreq::post("https://api.live.bilibili.com/room/v1/Room/stopLive")
-        .with_header("User-Agent", DEFAULT_USER_AGENT)
-        .with_header("Content-Type", "application/x-www-form-urlencoded")
-        .with_header("Cookie", &format!("SESSDATA={}", cookies.sessdata))
-        .with_body(form_data)
-        .send()?;
-
-    let response_text = response.as_str()?;
-    let res: serde_json::Value = serde_json::from_str(response_text)?;
-
-    if res["code"].as_i64() != Some(0) {
-        return Err(BiliLiveError::ApiError(format!("API返回错误: {}", res["message"].as_str().unwrap_or("未知错误"))));
-    }
-  
+pub fn stop_live(client: &BilibiliClient) -> Result<()> {
+    client.stop_live()?;
     user_success!("成功关闭直播");
-
-    get_live_info(live_id)?;
-
     Ok(())
 }
 
-fn get_live_info(live_id: u64) -> Result<()> {
-    let cookies = read_cookies()?;
-    let url = format!("https://api.live.bilibili.com/xlive/app-blink/v1/live/StopLiveData?live_key={}", live_id);
-  
-    let response = minreq::get(&url)
-        .with_header("User-Agent", DEFAULT_USER_AGENT)
-        .with_header("Content-Type", "application/json, text/plain, */*")
-        .with_header("Cookie", &format!("SESSDATA={}", cookies.sessdata))
-        .send()?;
-
-    let response_text = response.as_str()?;
-    let res: serde_json::Value = serde_json::from_str(response_text)?;
-  
-    if res["code"].as_i64() != Some(0) {
-        return Err(BiliLiveError::ApiError(format!("API返回错误: {}", res["message"].as_str().unwrap_or("未知错误")).into()));
-    }
-  
-    let data = &res["data"];
-    user_info!("直播统计信息:");
-    user_info!("新增粉丝 : {}", data["AddFans"].as_i64().unwrap_or(0));
-    user_info!("弹幕数 : {}", data["DanmuNum"].as_i64().unwrap_or(0));
-    user_info!("金仓鼠流水 : {}", data["HamsterRmb"].as_i64().unwrap_or(0));
-    user_info!("直播时长 : {}", data["LiveTime"].as_i64().unwrap_or(0));
-    user_info!("最大在线 : {}", data["MaxOnline"].as_i64().unwrap_or(0));
-    user_info!("新增粉丝勋章 : {}", data["NewFansClub"].as_i64().unwrap_or(0));
-    user_info!("累计观看 : {}", data["WatchedCount"].as_i64().unwrap_or(0));
+/// 下播后获取本场统计，失败时只给出警告，不影响下播的结果
+pub fn try_get_live_info(client: &BilibiliClient, live_key: &str) -> Option<StopLiveData> {
+    get_live_info(client, live_key)
+        .inspect_err(|e| user_warning!("获取直播统计失败: {}，可稍后运行 bili_live stats {} 查询", e, live_key))
+        .ok()
+}
 
-    Ok(())
-}
\ No newline at end of file
+pub fn get_live_info(client: &BilibiliClient, live_key: &str) -> Result<StopLiveData> {
+    let data = client.live_stats(live_key)?;
+    user_info!("直播统计信息:");
+    user_info!("新增粉丝 : {}", data.add_fans);
+    user_info!("弹幕数 : {}", data.danmu_num);
+    user_info!("金仓鼠流水 : {}", data.hamster_rmb);
+    user_info!("直播时长 : {}", data.live_time);
+    user_info!("最大在线 : {}", data.max_online);
+    user_info!("新增粉丝勋章 : {}", data.new_fans_club);
+    user_info!("累计观看 : {}", data.watched_count);
+
+    Ok(data)
+}
@@ -0,0 +1,1783 @@
+mod common;
+
+use common::{MockServer, QrStep, Reply};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+fn bili_live(server: &MockServer, dir: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_bili_live"));
+    command
+        .current_dir(dir)
+        .env("BILI_LIVE_PASSPORT_URL", server.url())
+        .env("BILI_LIVE_WEB_URL", server.url())
+        .env("BILI_LIVE_LIVE_URL", server.url())
+        .env("BILI_LIVE_WWW_URL", server.url())
+        .env("BILI_LIVE_CONFIG_DIR", dir)
+        .env("BILI_LIVE_STATE_DIR", dir)
+        .env("BILI_LIVE_INSECURE_PLAINTEXT", "true")
+        .env_remove("BILI_LIVE_PASSPHRASE")
+        .env_remove("BILI_LIVE_KEY_FILE")
+        .env_remove("BILI_LIVE_CONFIG")
+        .env_remove("DISPLAY")
+        .env_remove("WAYLAND_DISPLAY")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    command
+}
+
+fn write_cookies(dir: &Path) {
+    let cookies = serde_json::json!({
+        "room_id": common::ROOM_ID,
+        "refresh_token": common::REFRESH_TOKEN,
+        "cookies": {
+            "SESSDATA": { "value": common::SESSDATA },
+            "bili_jct": { "value": common::CSRF },
+            "DedeUserID": { "value": common::MID.to_string() },
+        },
+    });
+    std::fs::write(dir.join("cookies.json"), cookies.to_string()).unwrap();
+}
+
+fn write_session(dir: &Path, live_key: &str, area_id: u32) {
+    let session = serde_json::json!({
+        "room_id": common::ROOM_ID,
+        "live_key": live_key,
+        "area": { "id": area_id, "name": null },
+        "started_at": "2025-01-01T20:00:00+08:00",
+    });
+    std::fs::write(dir.join("session.json"), session.to_string()).unwrap();
+}
+
+fn read_session(dir: &Path) -> serde_json::Value {
+    serde_json::from_str(&std::fs::read_to_string(dir.join("session.json")).unwrap()).unwrap()
+}
+
+/// 正在运行的 bili_live 进程，逐行收集标准输出与标准错误
+struct Running {
+    child: Child,
+    lines: Receiver<(bool, String)>,
+    output: Vec<String>,
+    stdout: Vec<String>,
+}
+
+/// 已退出的进程
+struct Finished {
+    status: ExitStatus,
+    /// 标准输出与标准错误按到达顺序合并后的内容
+    output: String,
+    /// 仅标准输出的各行
+    stdout: Vec<String>,
+}
+
+impl Running {
+    fn spawn(command: &mut Command, input: &str) -> Self {
+        let mut child = command.spawn().expect("启动 bili_live 失败");
+        child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+
+        let (tx, lines) = mpsc::channel();
+        for (is_stdout, reader) in [
+            (true, Box::new(child.stdout.take().unwrap()) as Box<dyn std::io::Read + Send>),
+            (false, Box::new(child.stderr.take().unwrap())),
+        ] {
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                for line in BufReader::new(reader).lines().map_while(|l| l.ok()) {
+                    if tx.send((is_stdout, line)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        Self { child, lines, output: Vec::new(), stdout: Vec::new() }
+    }
+
+    fn record(&mut self, (is_stdout, line): (bool, String)) {
+        if is_stdout {
+            self.stdout.push(line.clone());
+        }
+        self.output.push(line);
+    }
+
+    /// 等待输出中出现指定内容
+    fn wait_for(&mut self, needle: &str) {
+        if self.output.iter().any(|line| line.contains(needle)) {
+            return;
+        }
+        let deadline = Instant::now() + TIMEOUT;
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            match self.lines.recv_timeout(remaining) {
+                Ok(line) => {
+                    let found = line.1.contains(needle);
+                    self.record(line);
+                    if found {
+                        return;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+        panic!("等待输出 {:?} 超时，已有输出:\n{}", needle, self.output.join("\n"));
+    }
+
+    /// 等待进程退出，并返回退出状态与全部输出
+    fn finish(self) -> (ExitStatus, String) {
+        let finished = self.wait();
+        (finished.status, finished.output)
+    }
+
+    fn wait(mut self) -> Finished {
+        let deadline = Instant::now() + TIMEOUT;
+        let status = loop {
+            if let Some(status) = self.child.try_wait().unwrap() {
+                break status;
+            }
+            if Instant::now() > deadline {
+                let _ = self.child.kill();
+                panic!("进程未按时退出，已有输出:\n{}", self.output.join("\n"));
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        };
+        while let Ok(line) = self.lines.recv_timeout(Duration::from_millis(500)) {
+            self.record(line);
+        }
+        Finished { status, output: self.output.join("\n"), stdout: self.stdout }
+    }
+
+    #[cfg(unix)]
+    fn interrupt(&self) {
+        self.signal("INT");
+    }
+
+    #[cfg(unix)]
+    fn signal(&self, name: &str) {
+        let status = Command::new("kill")
+            .args([&format!("-{}", name), &self.child.id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn login_start_and_stop_end_to_end() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+
+    let mut running = Running::spawn(&mut bili_live(&server, dir.path()), "\n");
+    running.wait_for("请在本程序中按 Ctrl+C 关闭直播");
+
+    let saved: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("cookies.json")).unwrap()).unwrap();
+    assert_eq!(saved["room_id"], common::ROOM_ID);
+    assert_eq!(saved["cookies"]["SESSDATA"]["value"], common::SESSDATA);
+    assert_eq!(saved["cookies"]["bili_jct"]["value"], common::CSRF);
+    assert_eq!(saved["cookies"]["sid"]["value"], common::SID);
+    assert!(!dir.path().join("qrcode.png").exists());
+    assert!(server.is_live());
+    assert_eq!(server.live_area(), Some(235));
+    assert!(dir.path().join("session.json").exists());
+
+    running.interrupt();
+    let (status, output) = running.finish();
+
+    assert!(status.success(), "{}", output);
+    assert!(!server.is_live());
+    assert!(!dir.path().join("session.json").exists());
+    assert!(output.contains(common::RTMP_ADDR));
+    assert!(!output.contains(common::RTMP_CODE), "推流码默认应打码显示");
+    assert!(output.contains("弹幕数 : 42"));
+    let stats = server
+        .requests()
+        .into_iter()
+        .find(|r| r.path == "/xlive/app-blink/v1/live/StopLiveData")
+        .unwrap();
+    assert_eq!(stats.query, format!("live_key={}", common::LIVE_KEY));
+}
+
+#[cfg(unix)]
+#[test]
+fn area_picker_selects_second_level_area() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+
+    let mut running = Running::spawn(bili_live(&server, dir.path()).arg("--show-full-code"), "n\n1\n2\n");
+    running.wait_for("请在本程序中按 Ctrl+C 关闭直播");
+    assert_eq!(server.live_area(), Some(87));
+    assert_eq!(server.hits("/x/passport-login/web/qrcode/generate"), 0);
+
+    running.interrupt();
+    let (status, output) = running.finish();
+    assert!(status.success(), "{}", output);
+    assert!(output.contains(common::RTMP_CODE));
+}
+
+#[test]
+fn login_waits_for_scan_and_confirmation() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    server.script_qr(&[QrStep::Waiting, QrStep::Scanned, QrStep::Success]);
+    // 登录完成后让开播失败，使进程尽快退出
+    server.reply_once("/room/v1/Room/startLive", Reply::Error { code: 60024, message: "目标分区需要人脸认证".into() });
+
+    let (status, output) = Running::spawn(&mut bili_live(&server, dir.path()), "\n").finish();
+
+    assert!(!status.success());
+    assert!(output.contains("已处理，请在手机上确认登录"), "{}", output);
+    assert!(output.contains("目标分区需要人脸认证"), "{}", output);
+    assert_eq!(server.hits("/x/passport-login/web/qrcode/poll"), 3);
+    assert!(dir.path().join("cookies.json").exists());
+}
+
+#[test]
+fn expired_qr_code_aborts_login() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    server.script_qr(&[QrStep::Expired]);
+
+    let (status, output) = Running::spawn(&mut bili_live(&server, dir.path()), "\n").finish();
+
+    assert!(!status.success());
+    assert!(output.contains("二维码已失效"), "{}", output);
+    assert!(!dir.path().join("cookies.json").exists());
+    assert!(!dir.path().join("qrcode.png").exists());
+    assert_eq!(server.hits("/room/v1/Room/startLive"), 0);
+}
+
+#[test]
+fn expired_session_triggers_new_login() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("cookies.json"),
+        r#"{"room_id":4242,"sessdata":"stale","csrf_token":"stale"}"#,
+    )
+    .unwrap();
+    server.reply_once("/room/v1/Room/startLive", Reply::Error { code: -1, message: "stop here".into() });
+
+    let (_, output) = Running::spawn(&mut bili_live(&server, dir.path()), "\n").finish();
+
+    assert!(output.contains("登录状态异常"), "{}", output);
+    assert_eq!(server.hits("/x/passport-login/web/qrcode/generate"), 1);
+    let saved = std::fs::read_to_string(dir.path().join("cookies.json")).unwrap();
+    assert!(saved.contains(common::SESSDATA));
+}
+
+#[test]
+fn expired_session_is_refreshed_without_qr_login() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.expire_session();
+
+    let finished = Running::spawn(bili_live(&server, dir.path()).args(["status", "--output", "json"]), "").wait();
+
+    assert!(finished.status.success(), "{}", finished.output);
+    assert!(finished.output.contains("登录凭据已刷新"), "{}", finished.output);
+    let status: serde_json::Value = serde_json::from_str(&finished.stdout[0]).unwrap();
+    assert_eq!(status["logged_in"], true);
+    assert_eq!(server.hits("/x/passport-login/web/qrcode/generate"), 0);
+    let saved: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("cookies.json")).unwrap()).unwrap();
+    assert_eq!(saved["cookies"]["SESSDATA"]["value"], common::REFRESHED_SESSDATA);
+    assert_eq!(saved["cookies"]["bili_jct"]["value"], common::REFRESHED_CSRF);
+    assert_eq!(saved["cookies"]["DedeUserID"]["value"], common::MID.to_string());
+    assert_eq!(saved["refresh_token"], common::REFRESHED_TOKEN);
+}
+
+#[test]
+fn cookies_are_refreshed_when_server_asks() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.require_refresh();
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).arg("status"), "").finish();
+
+    assert!(status.success(), "{}", output);
+    assert_eq!(server.hits("/x/passport-login/web/confirm/refresh"), 1);
+    let saved = std::fs::read_to_string(dir.path().join("cookies.json")).unwrap();
+    assert!(saved.contains(common::REFRESHED_SESSDATA), "{}", saved);
+}
+
+#[test]
+fn refreshed_cookies_are_kept_when_confirm_fails() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.expire_session();
+    server.reply_once("/x/passport-login/web/confirm/refresh", Reply::Error { code: -101, message: "账号未登录".into() });
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).arg("status"), "").finish();
+
+    assert!(status.success(), "{}", output);
+    assert!(output.contains("确认刷新登录凭据失败"), "{}", output);
+    assert_eq!(server.hits("/x/passport-login/web/qrcode/generate"), 0);
+    let saved: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("cookies.json")).unwrap()).unwrap();
+    assert_eq!(saved["cookies"]["SESSDATA"]["value"], common::REFRESHED_SESSDATA);
+    assert_eq!(saved["refresh_token"], common::REFRESHED_TOKEN);
+}
+
+#[test]
+fn failed_refresh_falls_back_to_qr_login() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.expire_session();
+    server.reply_once("/x/passport-login/web/cookie/refresh", Reply::Error { code: 86095, message: "refresh_csrf 错误".into() });
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).args(["status"]), "").finish();
+
+    assert!(status.success(), "{}", output);
+    assert!(output.contains("refresh_csrf 错误"), "{}", output);
+    assert!(output.contains("未登录"), "{}", output);
+}
+
+#[test]
+fn api_error_code_is_reported() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.reply_once("/room/v1/Room/startLive", Reply::Error { code: 60013, message: "非常抱歉，您所在的地区受实名认证限制无法开播".into() });
+
+    let (status, output) = Running::spawn(&mut bili_live(&server, dir.path()), "\n").finish();
+
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("实名认证限制"), "{}", output);
+    assert!(output.contains("60013"), "{}", output);
+    assert!(!server.is_live());
+}
+
+#[test]
+fn malformed_json_is_reported() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.reply_once("/x/web-interface/nav", Reply::Raw("<html>502 Bad Gateway</html>".into()));
+
+    let (status, output) = Running::spawn(&mut bili_live(&server, dir.path()), "\n").finish();
+
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("JSON解析失败"), "{}", output);
+    assert_eq!(server.hits("/room/v1/Room/startLive"), 0);
+}
+
+#[test]
+fn config_file_overrides_default_endpoints() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    std::fs::write(
+        dir.path().join("bili_live.toml"),
+        format!("[api]\npassport_url = \"{0}\"\nweb_url = \"{0}/\"\nlive_url = \"{0}\"\n", server.url()),
+    )
+    .unwrap();
+    server.reply_once("/room/v1/Room/startLive", Reply::Error { code: -1, message: "stop here".into() });
+
+    let mut command = bili_live(&server, dir.path());
+    command
+        .env_remove("BILI_LIVE_PASSPORT_URL")
+        .env_remove("BILI_LIVE_WEB_URL")
+        .env_remove("BILI_LIVE_LIVE_URL");
+    let (_, output) = Running::spawn(&mut command, "\n").finish();
+
+    assert!(output.contains("登录状态正常"), "{}", output);
+    assert_eq!(server.hits("/room/v1/Room/startLive"), 1);
+}
+
+#[test]
+fn area_flag_skips_all_prompts() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.reply_once("/room/v1/Room/startLive", Reply::Error { code: -1, message: "stop here".into() });
+
+    // 标准输入直接关闭，若程序仍尝试读取输入则会报错
+    let (_, output) = Running::spawn(bili_live(&server, dir.path()).args(["--area", "86", "--no-prompt"]), "").finish();
+
+    assert!(output.contains("使用指定的分区: 86"), "{}", output);
+    let start = server.requests().into_iter().find(|r| r.path == "/room/v1/Room/startLive").unwrap();
+    assert!(start.body.contains("area_v2=86"), "{}", start.body);
+    assert_eq!(server.hits("/room/v1/Area/getMyChooseArea"), 0);
+}
+
+#[test]
+fn use_last_area_flag_skips_question() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.reply_once("/room/v1/Room/startLive", Reply::Error { code: -1, message: "stop here".into() });
+
+    let (_, output) = Running::spawn(bili_live(&server, dir.path()).args(["--use-last-area", "--no-prompt"]), "").finish();
+
+    assert!(output.contains("使用上次的分区: 其他单机 - 235"), "{}", output);
+    assert!(!output.contains("常用分区"), "{}", output);
+}
+
+#[test]
+fn use_last_area_without_recent_area_points_to_area_flag() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.reply_once(
+        "/room/v1/Area/getMyChooseArea",
+        Reply::Json(serde_json::json!({ "code": 0, "message": "0", "data": [] })),
+    );
+
+    let (status, output) =
+        Running::spawn(bili_live(&server, dir.path()).args(["--use-last-area", "--no-prompt"]), "").finish();
+
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("分区选择失败: 没有最近直播过的分区，请使用 --area 指定分区"), "{}", output);
+    assert!(!output.contains("数据解析失败"), "{}", output);
+    assert_eq!(server.hits("/room/v1/Room/startLive"), 0);
+}
+
+#[test]
+fn no_prompt_without_area_fails_fast() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).arg("--no-prompt"), "\n").finish();
+
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("--area"), "{}", output);
+    assert_eq!(server.hits("/room/v1/Room/startLive"), 0);
+}
+
+#[test]
+fn no_prompt_refuses_qr_login() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).args(["--no-prompt", "--area", "86"]), "").finish();
+
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("需要扫码登录"), "{}", output);
+    assert_eq!(server.hits("/x/passport-login/web/qrcode/generate"), 0);
+}
+
+#[test]
+fn closed_stdin_at_prompt_is_an_error() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+
+    let (status, output) = Running::spawn(&mut bili_live(&server, dir.path()), "").finish();
+
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("标准输入已关闭"), "{}", output);
+    assert_eq!(server.hits("/room/v1/Room/startLive"), 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn json_output_reports_start_and_stop() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+
+    let mut running = Running::spawn(bili_live(&server, dir.path()).args(["--output", "json", "--use-last-area"]), "");
+    running.wait_for("请在本程序中按 Ctrl+C 关闭直播");
+    running.interrupt();
+    let finished = running.wait();
+
+    assert!(finished.status.success(), "{}", finished.output);
+    assert_eq!(finished.stdout.len(), 2, "stdout 只应包含 JSON 文档: {:?}", finished.stdout);
+    let started: serde_json::Value = serde_json::from_str(&finished.stdout[0]).unwrap();
+    assert_eq!(started["event"], "started");
+    assert_eq!(started["room_id"], common::ROOM_ID);
+    assert_eq!(started["area"]["id"], 235);
+    assert_eq!(started["area"]["name"], "其他单机");
+    assert_eq!(started["rtmp_addr"], common::RTMP_ADDR);
+    assert_eq!(started["rtmp_code"], common::RTMP_CODE);
+    assert_eq!(started["live_key"], common::LIVE_KEY);
+
+    let stopped: serde_json::Value = serde_json::from_str(&finished.stdout[1]).unwrap();
+    assert_eq!(stopped["event"], "stopped");
+    assert_eq!(stopped["stats"]["danmu_num"], 42);
+    assert_eq!(stopped["stats"]["watched_count"], 128);
+}
+
+#[test]
+fn env_output_is_shell_quoted() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+
+    let mut running = Running::spawn(bili_live(&server, dir.path()).args(["--output", "env", "--area", "86"]), "");
+    running.wait_for("请在本程序中按 Ctrl+C 关闭直播");
+    let _ = running.child.kill();
+    let finished = running.wait();
+
+    assert!(finished.stdout.contains(&"BILI_LIVE_ROOM_ID='4242'".to_string()), "{:?}", finished.stdout);
+    assert!(finished.stdout.contains(&"BILI_LIVE_AREA_ID='86'".to_string()), "{:?}", finished.stdout);
+    assert!(finished.stdout.contains(&format!("BILI_LIVE_RTMP_CODE='{}'", common::RTMP_CODE)), "{:?}", finished.stdout);
+    assert!(finished.stdout.iter().all(|line| line.starts_with("BILI_LIVE_")), "{:?}", finished.stdout);
+}
+
+#[test]
+fn login_subcommand_only_logs_in() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).arg("login"), "").finish();
+
+    assert!(status.success(), "{}", output);
+    assert!(dir.path().join("cookies.json").exists());
+    assert_eq!(server.hits("/room/v1/Room/startLive"), 0);
+}
+
+#[test]
+fn logout_subcommand_removes_credentials() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).arg("logout"), "").finish();
+
+    assert!(status.success(), "{}", output);
+    assert_eq!(server.hits("/login/exit/v2"), 1);
+    assert!(!dir.path().join("cookies.json").exists());
+}
+
+#[test]
+fn status_subcommand_reports_live_room() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.set_live(true);
+
+    let finished = Running::spawn(bili_live(&server, dir.path()).args(["status", "--output", "json"]), "").wait();
+
+    assert!(finished.status.success(), "{}", finished.output);
+    let status: serde_json::Value = serde_json::from_str(&finished.stdout[0]).unwrap();
+    assert_eq!(status["logged_in"], true);
+    assert_eq!(status["live"], true);
+    assert_eq!(status["room_id"], common::ROOM_ID);
+}
+
+#[test]
+fn status_subcommand_without_login() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+
+    let finished = Running::spawn(bili_live(&server, dir.path()).args(["status", "--output", "json"]), "").wait();
+
+    assert!(finished.status.success(), "{}", finished.output);
+    let status: serde_json::Value = serde_json::from_str(&finished.stdout[0]).unwrap();
+    assert_eq!(status["logged_in"], false);
+    assert_eq!(server.hits("/x/passport-login/web/qrcode/generate"), 0);
+}
+
+#[test]
+fn start_subcommand_accepts_start_flags() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.reply_once("/room/v1/Room/startLive", Reply::Error { code: -1, message: "stop here".into() });
+
+    let (_, output) = Running::spawn(bili_live(&server, dir.path()).args(["start", "--area", "236", "--no-prompt"]), "").finish();
+
+    assert!(output.contains("使用指定的分区: 236"), "{}", output);
+}
+
+#[test]
+fn stop_subcommand_stops_room_and_reports_stats() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.set_live(true);
+
+    let finished = Running::spawn(
+        bili_live(&server, dir.path()).args(["stop", "--live-key", common::LIVE_KEY, "--output", "json"]),
+        "",
+    )
+    .wait();
+
+    assert!(finished.status.success(), "{}", finished.output);
+    assert!(!server.is_live());
+    let stopped: serde_json::Value = serde_json::from_str(&finished.stdout[0]).unwrap();
+    assert_eq!(stopped["event"], "stopped");
+    assert_eq!(stopped["stats"]["max_online"], 17);
+}
+
+#[test]
+fn stop_subcommand_recovers_session_of_killed_process() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+
+    let mut running = Running::spawn(bili_live(&server, dir.path()).args(["--area", "236"]), "");
+    running.wait_for("请在本程序中按 Ctrl+C 关闭直播");
+    running.child.kill().unwrap();
+    running.wait();
+    assert!(server.is_live());
+
+    let session = read_session(dir.path());
+    assert_eq!(session["room_id"], common::ROOM_ID);
+    assert_eq!(session["live_key"], common::LIVE_KEY);
+    assert_eq!(session["area"]["id"], 236);
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).arg("stop"), "").finish();
+
+    assert!(status.success(), "{}", output);
+    assert!(!server.is_live());
+    assert!(output.contains("找到本地记录的直播场次"), "{}", output);
+    assert!(output.contains("弹幕数 : 42"), "{}", output);
+    assert!(!dir.path().join("session.json").exists());
+}
+
+#[test]
+fn stop_subcommand_ignores_session_of_other_room() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.set_live(true);
+    let session = serde_json::json!({
+        "room_id": 1,
+        "live_key": "other",
+        "area": { "id": 86, "name": null },
+        "started_at": "2025-01-01T20:00:00+08:00",
+    });
+    std::fs::write(dir.path().join("session.json"), session.to_string()).unwrap();
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).arg("stop"), "").finish();
+
+    assert!(status.success(), "{}", output);
+    assert!(output.contains("不一致"), "{}", output);
+    assert_eq!(server.hits("/xlive/app-blink/v1/live/StopLiveData"), 0);
+    assert!(dir.path().join("session.json").exists());
+}
+
+#[test]
+fn stop_subcommand_requires_login() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).arg("stop"), "").finish();
+
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("bili_live login"), "{}", output);
+    assert_eq!(server.hits("/room/v1/Room/stopLive"), 0);
+}
+
+#[test]
+fn areas_subcommand_lists_all_areas() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+
+    let finished = Running::spawn(bili_live(&server, dir.path()).args(["areas", "--output", "json"]), "").wait();
+
+    assert!(finished.status.success(), "{}", finished.output);
+    let areas: serde_json::Value = serde_json::from_str(&finished.stdout[0]).unwrap();
+    assert_eq!(areas[0]["list"][0]["id"], 86);
+    assert_eq!(areas[1]["list"][0]["name"], "其他单机");
+}
+
+#[test]
+fn stats_subcommand_prints_statistics() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).args(["stats", common::LIVE_KEY]), "").finish();
+
+    assert!(status.success(), "{}", output);
+    assert!(output.contains("累计观看 : 128"), "{}", output);
+    assert_eq!(server.hits("/room/v1/Room/stopLive"), 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn leftover_session_can_be_resumed() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    write_session(dir.path(), "oldkey", 86);
+    server.set_live(true);
+
+    let mut running = Running::spawn(&mut bili_live(&server, dir.path()), "\n");
+    running.wait_for("请在本程序中按 Ctrl+C 关闭直播");
+    assert_eq!(server.hits("/room/v1/Room/startLive"), 0);
+    assert_eq!(read_session(dir.path())["live_key"], "oldkey");
+
+    running.interrupt();
+    let (status, output) = running.finish();
+
+    assert!(status.success(), "{}", output);
+    assert!(output.contains("检测到上次未关闭的直播: oldkey"), "{}", output);
+    assert!(!server.is_live());
+    let stats = server
+        .requests()
+        .into_iter()
+        .find(|r| r.path == "/xlive/app-blink/v1/live/StopLiveData")
+        .unwrap();
+    assert_eq!(stats.query, "live_key=oldkey");
+}
+
+#[test]
+fn recover_stop_reports_old_stats_then_starts_again() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    write_session(dir.path(), "oldkey", 86);
+    server.set_live(true);
+
+    let mut running = Running::spawn(
+        bili_live(&server, dir.path()).args(["--recover", "stop", "--area", "236", "--no-prompt", "--output", "json"]),
+        "",
+    );
+    running.wait_for("请在本程序中按 Ctrl+C 关闭直播");
+    running.child.kill().unwrap();
+    let finished = running.wait();
+
+    let stopped: serde_json::Value = serde_json::from_str(&finished.stdout[0]).unwrap();
+    assert_eq!(stopped["event"], "stopped");
+    assert_eq!(stopped["live_key"], "oldkey");
+    let started: serde_json::Value = serde_json::from_str(&finished.stdout[1]).unwrap();
+    assert_eq!(started["event"], "started");
+    assert_eq!(server.hits("/room/v1/Room/stopLive"), 1);
+    assert_eq!(server.live_area(), Some(236));
+    assert_eq!(read_session(dir.path())["live_key"], common::LIVE_KEY);
+}
+
+#[test]
+fn recover_adopt_takes_over_running_live() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    write_session(dir.path(), "oldkey", 86);
+    server.set_live(true);
+
+    let mut running = Running::spawn(bili_live(&server, dir.path()).args(["--recover", "adopt", "--no-prompt"]), "");
+    running.wait_for("请在本程序中按 Ctrl+C 关闭直播");
+    running.child.kill().unwrap();
+    let finished = running.wait();
+
+    assert!(finished.output.contains(common::RTMP_ADDR), "{}", finished.output);
+    assert_eq!(server.hits("/room/v1/Room/stopLive"), 0);
+    assert_eq!(server.live_area(), Some(86));
+    let session = read_session(dir.path());
+    assert_eq!(session["live_key"], common::LIVE_KEY);
+    let started_at = chrono::DateTime::parse_from_rfc3339(session["started_at"].as_str().unwrap()).unwrap();
+    assert_eq!(started_at, chrono::DateTime::parse_from_rfc3339("2025-01-01T20:00:00+08:00").unwrap());
+}
+
+#[test]
+fn live_room_without_recover_fails_in_no_prompt_mode() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.set_live(true);
+
+    let (status, output) =
+        Running::spawn(bili_live(&server, dir.path()).args(["--area", "236", "--no-prompt"]), "").finish();
+
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("--recover"), "{}", output);
+    assert_eq!(server.hits("/room/v1/Room/startLive"), 0);
+}
+
+#[test]
+fn finished_leftover_session_only_reports_stats() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    write_session(dir.path(), "oldkey", 86);
+    server.reply_once("/room/v1/Room/startLive", Reply::Error { code: -1, message: "stop here".into() });
+
+    let (_, output) = Running::spawn(bili_live(&server, dir.path()).args(["--area", "236", "--no-prompt"]), "").finish();
+
+    assert!(output.contains("上次的直播 oldkey 已结束"), "{}", output);
+    assert!(output.contains("弹幕数 : 42"), "{}", output);
+    assert_eq!(server.hits("/room/v1/Room/stopLive"), 0);
+    assert!(!dir.path().join("session.json").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn termination_signals_stop_the_live() {
+    for signal in ["TERM", "HUP"] {
+        let server = MockServer::start();
+        let dir = TempDir::new().unwrap();
+        write_cookies(dir.path());
+
+        let mut running = Running::spawn(bili_live(&server, dir.path()).args(["--area", "236"]), "");
+        running.wait_for("请在本程序中按 Ctrl+C 关闭直播");
+        running.signal(signal);
+        let (status, output) = running.finish();
+
+        assert!(status.success(), "SIG{}: {}", signal, output);
+        assert!(!server.is_live(), "SIG{}", signal);
+        assert!(output.contains("弹幕数 : 42"), "{}", output);
+        assert!(!dir.path().join("session.json").exists());
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn failed_stop_on_signal_exits_with_error() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.reply_once("/room/v1/Room/stopLive", Reply::Error { code: -400, message: "下播失败".into() });
+
+    let mut running = Running::spawn(bili_live(&server, dir.path()).args(["--area", "236"]), "");
+    running.wait_for("请在本程序中按 Ctrl+C 关闭直播");
+    running.signal("TERM");
+    let (status, output) = running.finish();
+
+    assert_eq!(status.code(), Some(1), "{}", output);
+    assert!(output.contains("下播失败"), "{}", output);
+    assert!(dir.path().join("session.json").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn hanging_stop_times_out() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.reply_once("/room/v1/Room/stopLive", Reply::Delay(Duration::from_secs(3)));
+
+    let mut running =
+        Running::spawn(bili_live(&server, dir.path()).args(["--area", "236", "--shutdown-timeout", "1"]), "");
+    running.wait_for("请在本程序中按 Ctrl+C 关闭直播");
+    running.signal("TERM");
+    let (status, output) = running.finish();
+
+    assert_eq!(status.code(), Some(124), "{}", output);
+    assert!(output.contains("关闭直播超时"), "{}", output);
+    assert!(dir.path().join("session.json").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn stats_failure_after_stop_still_succeeds() {
+    for reply in [
+        Reply::Error { code: -1, message: "统计暂不可用".into() },
+        Reply::Delay(Duration::from_secs(3)),
+    ] {
+        let server = MockServer::start();
+        let dir = TempDir::new().unwrap();
+        write_cookies(dir.path());
+        server.reply_once("/xlive/app-blink/v1/live/StopLiveData", reply);
+
+        let mut running =
+            Running::spawn(bili_live(&server, dir.path()).args(["--area", "236", "--shutdown-timeout", "1"]), "");
+        running.wait_for("请在本程序中按 Ctrl+C 关闭直播");
+        running.signal("TERM");
+        let (status, output) = running.finish();
+
+        assert!(status.success(), "{}", output);
+        assert!(!server.is_live());
+        assert!(output.contains("获取直播统计"), "{}", output);
+        assert!(output.contains(&format!("bili_live stats {}", common::LIVE_KEY)), "{}", output);
+        assert!(!dir.path().join("session.json").exists());
+    }
+}
+
+#[test]
+fn profiles_keep_separate_credentials_and_preferred_area() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+
+    let (status, output) =
+        Running::spawn(bili_live(&server, dir.path()).args(["profile", "add", "alice", "--area", "236"]), "").finish();
+    assert!(status.success(), "{}", output);
+    assert!(dir.path().join("profiles/alice/cookies.json").exists());
+
+    let finished = Running::spawn(bili_live(&server, dir.path()).args(["profile", "list", "--output", "json"]), "").wait();
+    let profiles: serde_json::Value = serde_json::from_str(&finished.stdout[0]).unwrap();
+    assert_eq!(profiles[0]["name"], "default");
+    assert_eq!(profiles[0]["default"], true);
+    assert_eq!(profiles[1]["name"], "alice");
+    assert_eq!(profiles[1]["preferred_area"]["id"], 236);
+
+    server.reply_once("/room/v1/Room/startLive", Reply::Error { code: -1, message: "stop here".into() });
+    let (_, output) =
+        Running::spawn(bili_live(&server, dir.path()).args(["--profile", "alice", "--no-prompt"]), "").finish();
+    assert!(output.contains("使用账号 alice 的偏好分区: 236"), "{}", output);
+
+    let (status, _) = Running::spawn(bili_live(&server, dir.path()).args(["profile", "default", "alice"]), "").finish();
+    assert!(status.success());
+    let finished = Running::spawn(bili_live(&server, dir.path()).args(["status", "--output", "json"]), "").wait();
+    let report: serde_json::Value = serde_json::from_str(&finished.stdout[0]).unwrap();
+    assert_eq!(report["profile"], "alice");
+    assert_eq!(report["logged_in"], true);
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).args(["profile", "remove", "alice"]), "").finish();
+    assert!(status.success(), "{}", output);
+    assert!(!dir.path().join("profiles/alice").exists());
+    assert!(dir.path().join("cookies.json").exists());
+    let finished = Running::spawn(bili_live(&server, dir.path()).args(["status", "--output", "json"]), "").wait();
+    let report: serde_json::Value = serde_json::from_str(&finished.stdout[0]).unwrap();
+    assert_eq!(report["profile"], "default");
+}
+
+#[test]
+fn profile_names_cannot_escape_the_data_dir() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).args(["--profile", "../evil", "status"]), "").finish();
+
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("无效的账号名"), "{}", output);
+}
+
+#[test]
+fn removing_unknown_profile_fails() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).args(["profile", "remove", "bob"]), "").finish();
+
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("账号 bob 不存在"), "{}", output);
+}
+
+#[test]
+fn legacy_files_move_to_the_xdg_dirs() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    write_session(dir.path(), common::LIVE_KEY, 86);
+    let config_dir = dir.path().join("xdg-config/bili_live");
+    let state_dir = dir.path().join("xdg-state/bili_live");
+
+    let mut command = bili_live(&server, dir.path());
+    command
+        .env_remove("BILI_LIVE_CONFIG_DIR")
+        .env_remove("BILI_LIVE_STATE_DIR")
+        .env("XDG_CONFIG_HOME", dir.path().join("xdg-config"))
+        .env("XDG_STATE_HOME", dir.path().join("xdg-state"))
+        .args(["status"]);
+    let (status, output) = Running::spawn(&mut command, "").finish();
+
+    assert!(status.success(), "{}", output);
+    assert!(output.contains("迁移"), "{}", output);
+    assert!(output.contains("已登录"), "{}", output);
+    assert!(!dir.path().join("cookies.json").exists());
+    assert!(!dir.path().join("session.json").exists());
+    assert!(config_dir.join("cookies.json").exists());
+    assert!(state_dir.join("session.json").exists());
+}
+
+#[test]
+fn config_dir_flag_overrides_the_default_location() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    let config_dir = dir.path().join("elsewhere");
+    std::fs::create_dir(&config_dir).unwrap();
+    write_cookies(&config_dir);
+
+    let (status, output) = Running::spawn(
+        bili_live(&server, dir.path())
+            .env_remove("BILI_LIVE_CONFIG_DIR")
+            .arg("--config-dir")
+            .arg(&config_dir)
+            .arg("status"),
+        "",
+    )
+    .finish();
+
+    assert!(status.success(), "{}", output);
+    assert!(output.contains("已登录"), "{}", output);
+    assert!(!output.contains("迁移"), "{}", output);
+}
+
+/// 去掉测试默认的明文模式，使用加密保存的凭据
+fn encrypted(command: &mut Command) -> &mut Command {
+    command.env_remove("BILI_LIVE_INSECURE_PLAINTEXT")
+}
+
+#[cfg(unix)]
+#[test]
+fn login_saves_encrypted_credentials() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+
+    let (status, output) = Running::spawn(encrypted(&mut bili_live(&server, dir.path())).arg("login"), "secret\nsecret\n").finish();
+    assert!(status.success(), "{}", output);
+
+    let saved = std::fs::read_to_string(dir.path().join("cookies.json")).unwrap();
+    assert!(saved.contains("xchacha20poly1305"), "{}", saved);
+    assert!(!saved.contains(common::SESSDATA), "{}", saved);
+    let mode = std::fs::metadata(dir.path().join("cookies.json")).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let (status, output) = Running::spawn(
+        encrypted(&mut bili_live(&server, dir.path())).env("BILI_LIVE_PASSPHRASE", "secret").arg("status"),
+        "",
+    )
+    .finish();
+    assert!(status.success(), "{}", output);
+    assert!(output.contains("已登录"), "{}", output);
+
+    let (status, output) = Running::spawn(
+        encrypted(&mut bili_live(&server, dir.path())).env("BILI_LIVE_PASSPHRASE", "wrong").arg("status"),
+        "",
+    )
+    .finish();
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("口令错误"), "{}", output);
+}
+
+#[test]
+fn mismatched_passphrase_confirmation_is_rejected() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+
+    let (status, output) = Running::spawn(encrypted(&mut bili_live(&server, dir.path())).arg("login"), "secret\nsecrte\n").finish();
+
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("两次输入的口令不一致"), "{}", output);
+    assert!(!dir.path().join("cookies.json").exists());
+}
+
+#[test]
+fn plaintext_credentials_are_encrypted_with_key_file() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    let key_file = dir.path().join("bili_live.key");
+    std::fs::write(&key_file, "0123456789abcdef0123456789abcdef").unwrap();
+
+    for _ in 0..2 {
+        let (status, output) = Running::spawn(
+            encrypted(&mut bili_live(&server, dir.path())).arg("--key-file").arg(&key_file).args(["--no-prompt", "status"]),
+            "",
+        )
+        .finish();
+        assert!(status.success(), "{}", output);
+        assert!(output.contains("已登录"), "{}", output);
+    }
+
+    let saved = std::fs::read_to_string(dir.path().join("cookies.json")).unwrap();
+    assert!(!saved.contains(common::SESSDATA), "{}", saved);
+}
+
+#[test]
+fn encrypted_credentials_need_passphrase_without_prompt() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    let (status, output) = Running::spawn(
+        encrypted(&mut bili_live(&server, dir.path())).env("BILI_LIVE_PASSPHRASE", "secret").arg("status"),
+        "",
+    )
+    .finish();
+    assert!(status.success(), "{}", output);
+
+    let (status, output) =
+        Running::spawn(encrypted(&mut bili_live(&server, dir.path())).args(["--no-prompt", "status"]), "").finish();
+
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("BILI_LIVE_PASSPHRASE"), "{}", output);
+}
+
+#[test]
+fn login_replaces_credentials_that_cannot_be_decrypted() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    let login = |passphrase: &str| {
+        Running::spawn(encrypted(&mut bili_live(&server, dir.path())).env("BILI_LIVE_PASSPHRASE", passphrase).arg("login"), "")
+            .finish()
+    };
+    let (status, output) = login("a");
+    assert!(status.success(), "{}", output);
+
+    let (status, output) = login("b");
+    assert!(status.success(), "{}", output);
+    assert!(output.contains("口令错误"), "{}", output);
+
+    let (status, output) = Running::spawn(
+        encrypted(&mut bili_live(&server, dir.path())).env("BILI_LIVE_PASSPHRASE", "b").args(["--no-prompt", "status"]),
+        "",
+    )
+    .finish();
+    assert!(status.success(), "{}", output);
+    assert!(output.contains("已登录"), "{}", output);
+}
+
+#[test]
+fn logout_removes_credentials_that_cannot_be_decrypted() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    let (status, output) = Running::spawn(
+        encrypted(&mut bili_live(&server, dir.path())).env("BILI_LIVE_PASSPHRASE", "a").arg("login"),
+        "",
+    )
+    .finish();
+    assert!(status.success(), "{}", output);
+
+    let (status, output) = Running::spawn(
+        encrypted(&mut bili_live(&server, dir.path())).env("BILI_LIVE_PASSPHRASE", "b").arg("logout"),
+        "",
+    )
+    .finish();
+    assert!(status.success(), "{}", output);
+    assert!(output.contains("口令错误"), "{}", output);
+    assert!(!dir.path().join("cookies.json").exists());
+
+    let (status, output) = Running::spawn(
+        encrypted(&mut bili_live(&server, dir.path())).env("BILI_LIVE_PASSPHRASE", "a").arg("login"),
+        "",
+    )
+    .finish();
+    assert!(status.success(), "{}", output);
+    let (status, output) =
+        Running::spawn(encrypted(&mut bili_live(&server, dir.path())).args(["--no-prompt", "logout"]), "").finish();
+    assert!(status.success(), "{}", output);
+    assert!(output.contains("BILI_LIVE_PASSPHRASE"), "{}", output);
+    assert!(!dir.path().join("cookies.json").exists());
+    assert_eq!(server.hits("/login/exit/v2"), 0);
+}
+
+#[test]
+fn insecure_plaintext_keeps_credentials_readable() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+
+    let (status, output) = Running::spawn(
+        encrypted(&mut bili_live(&server, dir.path())).args(["--insecure-plaintext", "--no-prompt", "login"]),
+        "",
+    )
+    .finish();
+
+    assert!(status.success(), "{}", output);
+    let saved = std::fs::read_to_string(dir.path().join("cookies.json")).unwrap();
+    assert!(saved.contains(common::SESSDATA), "{}", saved);
+}
+
+#[test]
+fn config_file_supplies_start_defaults() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    std::fs::write(dir.path().join("bili_live.toml"), "area = 86\nshow_full_code = true\nclipboard = false\n").unwrap();
+    server.reply_once("/room/v1/Room/startLive", Reply::Error { code: -1, message: "stop here".into() });
+
+    let (_, output) = Running::spawn(bili_live(&server, dir.path()).arg("--no-prompt"), "").finish();
+
+    assert!(output.contains("使用配置文件中的默认分区: 86"), "{}", output);
+    let start = server.requests().into_iter().find(|r| r.path == "/room/v1/Room/startLive").unwrap();
+    assert!(start.body.contains("area_v2=86"), "{}", start.body);
+}
+
+#[cfg(unix)]
+#[test]
+fn start_flags_override_config_switches() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    std::fs::write(dir.path().join("bili_live.toml"), "area = 86\nshow_full_code = true\nclipboard = false\n").unwrap();
+
+    let mut running = Running::spawn(bili_live(&server, dir.path()).args(["--no-show-full-code", "--clipboard"]), "");
+    running.wait_for("请在本程序中按 Ctrl+C 关闭直播");
+    running.interrupt();
+    let (status, output) = running.finish();
+
+    assert!(status.success(), "{}", output);
+    assert!(output.contains(common::RTMP_ADDR), "{}", output);
+    assert!(!output.contains(common::RTMP_CODE), "{}", output);
+    assert!(output.contains("剪贴板"), "{}", output);
+}
+
+#[test]
+fn command_line_overrides_config_file() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("bili_live.toml"),
+        "area = 86\noutput = \"json\"\nlog_level = \"warn\"\n\n[timeouts]\nshutdown = 30\n",
+    )
+    .unwrap();
+
+    let finished = Running::spawn(bili_live(&server, dir.path()).args(["config", "--output", "env"]), "").wait();
+
+    assert!(finished.status.success(), "{}", finished.output);
+    for line in ["BILI_LIVE_AREA='86'", "BILI_LIVE_OUTPUT='env'", "BILI_LIVE_LOG_LEVEL='warn'", "BILI_LIVE_TIMEOUTS_SHUTDOWN='30'"] {
+        assert!(finished.stdout.contains(&line.to_string()), "{:?}", finished.stdout);
+    }
+    assert!(finished.stdout.contains(&format!("BILI_LIVE_API_WEB_URL='{}'", server.url())), "{:?}", finished.stdout);
+
+    let finished = Running::spawn(bili_live(&server, dir.path()).arg("config"), "").wait();
+    let settings: serde_json::Value = serde_json::from_str(&finished.stdout.join("\n")).unwrap();
+    assert_eq!(settings["output"], "json");
+}
+
+#[test]
+fn config_text_output_is_valid_toml_with_effective_settings() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+
+    let finished =
+        Running::spawn(bili_live(&server, dir.path()).env("BILI_LIVE_SHUTDOWN_TIMEOUT", "3").arg("config"), "").wait();
+
+    assert!(finished.status.success(), "{}", finished.output);
+    let settings: toml::Value = toml::from_str(&finished.stdout.join("\n")).unwrap();
+    assert_eq!(settings["timeouts"]["shutdown"].as_integer(), Some(3));
+}
+
+#[test]
+fn invalid_config_file_is_reported() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("bili_live.toml"), "area = \"music\"\n").unwrap();
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).arg("status"), "").finish();
+
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("配置文件解析失败"), "{}", output);
+}
+
+#[cfg(unix)]
+#[test]
+fn hooks_receive_live_details() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    std::fs::write(
+        dir.path().join("bili_live.toml"),
+        "[hooks]\non_start = \"echo $BILI_LIVE_RTMP_CODE > started.txt\"\non_stop = \"echo $BILI_LIVE_STATS_DANMU_NUM > stopped.txt\"\n",
+    )
+    .unwrap();
+
+    let mut running = Running::spawn(bili_live(&server, dir.path()).args(["--area", "86", "--output", "json"]), "");
+    running.wait_for("请在本程序中按 Ctrl+C 关闭直播");
+    running.interrupt();
+    let finished = running.wait();
+
+    assert!(finished.status.success(), "{}", finished.output);
+    assert_eq!(finished.stdout.len(), 2, "钩子的输出不应混入标准输出: {:?}", finished.stdout);
+    let started = std::fs::read_to_string(dir.path().join("started.txt")).unwrap();
+    assert_eq!(started.trim(), common::RTMP_CODE);
+    let stopped = std::fs::read_to_string(dir.path().join("stopped.txt")).unwrap();
+    assert_eq!(stopped.trim(), "42");
+}
+
+#[cfg(unix)]
+#[test]
+fn hanging_stop_hook_is_killed_after_timeout() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    std::fs::write(dir.path().join("bili_live.toml"), "[hooks]\non_stop = \"sleep 30\"\n").unwrap();
+
+    let mut running =
+        Running::spawn(bili_live(&server, dir.path()).args(["--area", "86", "--shutdown-timeout", "1"]), "");
+    running.wait_for("请在本程序中按 Ctrl+C 关闭直播");
+    let signalled = std::time::Instant::now();
+    running.signal("TERM");
+    let (status, output) = running.finish();
+
+    assert!(status.success(), "{}", output);
+    assert!(signalled.elapsed() < Duration::from_secs(10), "{:?}", signalled.elapsed());
+    assert!(output.contains("下播钩子在下播超时前没有结束，已终止"), "{}", output);
+    assert!(!server.is_live());
+}
+
+#[cfg(unix)]
+#[test]
+fn stats_and_stop_hook_share_the_shutdown_deadline() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    std::fs::write(dir.path().join("bili_live.toml"), "[hooks]\non_stop = \"sleep 30\"\n").unwrap();
+    server.reply_once("/xlive/app-blink/v1/live/StopLiveData", Reply::Delay(Duration::from_secs(5)));
+
+    let mut running =
+        Running::spawn(bili_live(&server, dir.path()).args(["--area", "86", "--shutdown-timeout", "2"]), "");
+    running.wait_for("请在本程序中按 Ctrl+C 关闭直播");
+    let signalled = std::time::Instant::now();
+    running.signal("TERM");
+    let (status, output) = running.finish();
+
+    assert!(status.success(), "{}", output);
+    assert!(signalled.elapsed() < Duration::from_millis(3500), "{:?}", signalled.elapsed());
+    assert!(output.contains("获取直播统计超时"), "{}", output);
+    assert!(output.contains("下播钩子在下播超时前没有结束"), "{}", output);
+}
+
+#[test]
+fn title_is_updated_before_going_live() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.reply_once("/room/v1/Room/startLive", Reply::Error { code: -1, message: "stop here".into() });
+
+    let (_, output) = Running::spawn(
+        bili_live(&server, dir.path()).args(["--area", "86", "--title", "今晚 打排位 & 聊天", "--no-prompt"]),
+        "",
+    )
+    .finish();
+
+    assert!(output.contains("直播间标题已修改为: 今晚 打排位 & 聊天"), "{}", output);
+    assert_eq!(server.title(), "今晚 打排位 & 聊天");
+    let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+    let update = paths.iter().position(|p| p == "/room/v1/Room/update").unwrap();
+    let start = paths.iter().position(|p| p == "/room/v1/Room/startLive").unwrap();
+    assert!(update < start, "{:?}", paths);
+}
+
+#[test]
+fn title_defaults_to_config_file() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    std::fs::write(dir.path().join("bili_live.toml"), "area = 86\ntitle = \"配置中的标题\"\n").unwrap();
+    server.reply_once("/room/v1/Room/startLive", Reply::Error { code: -1, message: "stop here".into() });
+
+    Running::spawn(bili_live(&server, dir.path()).arg("--no-prompt"), "").finish();
+    assert_eq!(server.title(), "配置中的标题");
+
+    server.reply_once("/room/v1/Room/startLive", Reply::Error { code: -1, message: "stop here".into() });
+    Running::spawn(bili_live(&server, dir.path()).args(["--no-prompt", "--title", "命令行的标题"]), "").finish();
+    assert_eq!(server.title(), "命令行的标题");
+}
+
+#[test]
+fn title_subcommand_shows_and_changes_title() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.set_live(true);
+
+    let finished = Running::spawn(bili_live(&server, dir.path()).args(["title", "--output", "json"]), "").wait();
+    assert!(finished.status.success(), "{}", finished.output);
+    let report: serde_json::Value = serde_json::from_str(&finished.stdout[0]).unwrap();
+    assert_eq!(report["title"], "模拟直播间");
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).args(["title", "中途换个标题"]), "").finish();
+    assert!(status.success(), "{}", output);
+    assert_eq!(server.title(), "中途换个标题");
+    assert!(server.is_live());
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).args(["title", "  "]), "").finish();
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("直播间标题不能为空"), "{}", output);
+}
+
+#[test]
+fn room_show_reports_announcement_intro_and_tags() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+
+    let finished = Running::spawn(bili_live(&server, dir.path()).args(["room", "show", "--output", "json"]), "").wait();
+
+    assert!(finished.status.success(), "{}", finished.output);
+    let report: serde_json::Value = serde_json::from_str(&finished.stdout[0]).unwrap();
+    assert_eq!(report["room_id"], common::ROOM_ID);
+    assert_eq!(report["title"], "模拟直播间");
+    assert_eq!(report["announcement"], "每晚八点开播");
+    assert_eq!(report["description"], "这是一个模拟直播间");
+    assert_eq!(report["tags"], serde_json::json!(["游戏", "聊天"]));
+}
+
+#[test]
+fn room_set_updates_everything_in_one_go() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+
+    let (status, output) = Running::spawn(
+        bili_live(&server, dir.path()).args([
+            "room", "set",
+            "--title", "新标题",
+            "--announcement", "今晚 9 点 & 周末全天",
+            "--intro", "欢迎来玩",
+            "--tags", "音乐, 弹唱,",
+        ]),
+        "",
+    )
+    .finish();
+
+    assert!(status.success(), "{}", output);
+    assert_eq!(server.title(), "新标题");
+    let (news, description, tags) = server.room_profile();
+    assert_eq!(news, "今晚 9 点 & 周末全天");
+    assert_eq!(description, "欢迎来玩");
+    assert_eq!(tags, "音乐,弹唱");
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).args(["room", "set", "--announcement", ""]), "").finish();
+    assert!(status.success(), "{}", output);
+    assert_eq!(server.room_profile().0, "");
+}
+
+#[test]
+fn room_set_requires_something_to_change() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).args(["room", "set"]), "").finish();
+
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("请至少指定"), "{}", output);
+    assert_eq!(server.requests().len(), 0);
+}
+
+/// 生成指定尺寸的 PNG 图片
+fn write_png(path: &Path, width: u32, height: u32) {
+    image::RgbImage::from_pixel(width, height, image::Rgb([200, 80, 40])).save(path).unwrap();
+}
+
+#[test]
+fn cover_dry_run_crops_and_resizes_locally() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_png(&dir.path().join("square.png"), 1000, 1000);
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).args(["cover", "set", "square.png", "--dry-run"]), "").finish();
+
+    assert!(status.success(), "{}", output);
+    let processed = image::open(dir.path().join("square_cover.jpg")).unwrap();
+    assert_eq!((processed.width(), processed.height()), (1280, 720));
+    assert_eq!(server.requests().len(), 0);
+}
+
+#[test]
+fn cover_set_uploads_and_binds_cover() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    write_png(&dir.path().join("wide.png"), 1700, 720);
+
+    let finished = Running::spawn(bili_live(&server, dir.path()).args(["cover", "set", "wide.png", "--output", "json"]), "").wait();
+
+    assert!(finished.status.success(), "{}", finished.output);
+    assert_eq!(server.cover().as_deref(), Some(common::COVER_URL));
+    let report: serde_json::Value = serde_json::from_str(&finished.stdout[0]).unwrap();
+    assert_eq!(report["cover"], common::COVER_URL);
+    assert!(!dir.path().join("wide_cover.jpg").exists());
+}
+
+#[test]
+fn too_small_cover_is_rejected() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    write_png(&dir.path().join("tiny.png"), 1920, 300);
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).args(["cover", "set", "tiny.png"]), "").finish();
+
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("图片尺寸 1920x300 过小"), "{}", output);
+    assert_eq!(server.hits("/x/upload/web/image"), 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn switching_area_mid_stream_is_recorded_in_summary() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+
+    let mut running = Running::spawn(bili_live(&server, dir.path()).args(["--output", "json", "--area", "235"]), "");
+    running.wait_for("请在本程序中按 Ctrl+C 关闭直播");
+
+    let switched = Running::spawn(bili_live(&server, dir.path()).args(["switch-area", "--area", "86"]), "").wait();
+    assert!(switched.status.success(), "{}", switched.output);
+    assert_eq!(server.live_area(), Some(86));
+    assert_eq!(read_session(dir.path())["area"]["id"], 86);
+    assert_eq!(read_session(dir.path())["area"]["name"], "英雄联盟");
+    let unknown = Running::spawn(bili_live(&server, dir.path()).args(["switch-area", "--area", "1"]), "").wait();
+    assert!(!unknown.status.success());
+    assert!(unknown.output.contains("分区 1 不存在"), "{}", unknown.output);
+    assert_eq!(server.live_area(), Some(86));
+
+    running.interrupt();
+    let finished = running.wait();
+
+    assert!(finished.status.success(), "{}", finished.output);
+    assert!(finished.output.contains("分区记录:"), "{}", finished.output);
+    assert!(finished.output.contains("英雄联盟 - 86"), "{}", finished.output);
+    assert_eq!(server.hits("/room/v1/Room/startLive"), 1);
+    let stopped: serde_json::Value = serde_json::from_str(&finished.stdout[1]).unwrap();
+    let areas: Vec<_> = stopped["areas"].as_array().unwrap().iter().map(|s| s["area"]["id"].clone()).collect();
+    assert_eq!(areas, [235, 86]);
+}
+
+#[test]
+fn switch_area_picker_extends_legacy_session() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    write_session(dir.path(), "oldkey", 86);
+    server.set_live(true);
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).arg("switch-area"), "1\n2\n").finish();
+
+    assert!(status.success(), "{}", output);
+    assert_eq!(server.live_area(), Some(87));
+    let session = read_session(dir.path());
+    let segments = &session["segments"];
+    assert_eq!(segments[0]["area"]["id"], 86);
+    assert_eq!(segments[0]["started_at"], session["started_at"]);
+    assert_eq!(segments[1]["area"]["id"], 87);
+}
+
+#[test]
+fn switch_area_requires_live_room() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+
+    let (status, output) =
+        Running::spawn(bili_live(&server, dir.path()).args(["switch-area", "--area", "86"]), "").finish();
+
+    assert!(!status.success());
+    assert!(output.contains("直播间未开播"), "{}", output);
+    assert_eq!(server.hits("/room/v1/Room/update"), 0);
+}
+
+fn write_area_cache(dir: &Path, fetched_at: &str, etag: &str) {
+    let cache = serde_json::json!({
+        "fetched_at": fetched_at,
+        "etag": etag,
+        "areas": [{ "name": "缓存分区", "list": [{ "id": 999, "name": "只在缓存中" }] }],
+    });
+    std::fs::write(dir.join("areas.json"), cache.to_string()).unwrap();
+}
+
+#[test]
+fn areas_are_cached_for_search_and_show() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+
+    let listed = Running::spawn(bili_live(&server, dir.path()).args(["areas", "list"]), "").wait();
+    assert!(listed.status.success(), "{}", listed.output);
+    assert!(dir.path().join("areas.json").exists());
+
+    let searched =
+        Running::spawn(bili_live(&server, dir.path()).args(["areas", "search", "单机", "--output", "json"]), "").wait();
+    assert!(searched.status.success(), "{}", searched.output);
+    let found: serde_json::Value = serde_json::from_str(&searched.stdout[0]).unwrap();
+    assert_eq!(found.as_array().unwrap().len(), 1);
+    assert_eq!(found[0]["list"].as_array().unwrap().len(), 2);
+
+    let shown = Running::spawn(bili_live(&server, dir.path()).args(["areas", "show", "87", "--output", "json"]), "").wait();
+    assert!(shown.status.success(), "{}", shown.output);
+    let area: serde_json::Value = serde_json::from_str(&shown.stdout[0]).unwrap();
+    assert_eq!(area["name"], "守望先锋");
+    assert_eq!(area["parent_name"], "网游");
+
+    assert_eq!(server.hits("/room/v1/Area/getList"), 1);
+}
+
+#[test]
+fn expired_area_cache_is_revalidated_with_etag() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_area_cache(dir.path(), "2025-01-01T20:00:00+08:00", common::AREA_ETAG);
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).args(["areas", "show", "999"]), "").finish();
+
+    assert!(status.success(), "{}", output);
+    assert!(output.contains("分区列表没有变化"), "{}", output);
+    assert_eq!(server.hits("/room/v1/Area/getList"), 1);
+    let cache: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("areas.json")).unwrap()).unwrap();
+    assert_ne!(cache["fetched_at"], "2025-01-01T20:00:00+08:00");
+}
+
+#[test]
+fn refresh_flag_replaces_area_cache_and_unknown_id_fails() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_area_cache(dir.path(), &chrono::Local::now().to_rfc3339(), "\"stale\"");
+
+    let (status, output) =
+        Running::spawn(bili_live(&server, dir.path()).args(["areas", "--refresh", "show", "999"]), "").finish();
+
+    assert!(!status.success());
+    assert!(output.contains("分区 999 不存在"), "{}", output);
+    assert_eq!(server.hits("/room/v1/Area/getList"), 1);
+}
+
+#[test]
+fn stale_area_cache_is_used_when_offline() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_area_cache(dir.path(), "2025-01-01T20:00:00+08:00", common::AREA_ETAG);
+    server.reply_once("/room/v1/Area/getList", Reply::Error { code: -500, message: "服务暂不可用".to_string() });
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).args(["areas", "search", "缓存"]), "").finish();
+
+    assert!(status.success(), "{}", output);
+    assert!(output.contains("获取分区列表失败"), "{}", output);
+    assert!(output.contains("999 - 只在缓存中"), "{}", output);
+}
+
+#[test]
+fn area_picker_searches_names_and_pinyin_initials() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.set_live(true);
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).arg("switch-area"), "swxf\n1\n").finish();
+    assert!(status.success(), "{}", output);
+    assert!(output.contains("1. 守望先锋 (网游) - 87"), "{}", output);
+    assert_eq!(server.live_area(), Some(87));
+
+    // 名称匹配的排在只有一级分区匹配的前面
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).arg("switch-area"), "单机\n2\n").finish();
+    assert!(status.success(), "{}", output);
+    assert!(output.contains("1. 其他单机 (单机游戏) - 235"), "{}", output);
+    assert_eq!(server.live_area(), Some(236));
+}
+
+#[test]
+fn area_picker_falls_back_to_menu_without_matches() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.set_live(true);
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).arg("switch-area"), "xyz\n1\n2\n").finish();
+
+    assert!(status.success(), "{}", output);
+    assert!(output.contains("没有找到与 xyz 匹配的分区"), "{}", output);
+    assert_eq!(server.live_area(), Some(87));
+}
+
+#[test]
+fn area_picker_reprompts_on_invalid_input() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.set_live(true);
+
+    let (status, output) =
+        Running::spawn(bili_live(&server, dir.path()).arg("switch-area"), "\nb\n9\n1\na\n5\nb\n2\n1\n").finish();
+
+    assert!(status.success(), "{}", output);
+    assert!(output.contains("无效的选择，请重新输入"), "{}", output);
+    assert!(output.contains("已经在一级分区列表"), "{}", output);
+    assert_eq!(server.live_area(), Some(235));
+}
+
+#[test]
+fn area_picker_can_be_cancelled() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.set_live(true);
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).arg("switch-area"), "1\nq\n").finish();
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("已取消分区选择"), "{}", output);
+
+    // 标准输入关闭时不会反复提示
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).arg("switch-area"), "wz\n").finish();
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("标准输入已关闭，已取消分区选择"), "{}", output);
+    assert_eq!(server.hits("/room/v1/Room/update"), 0);
+}
+
+#[test]
+fn tui_needs_a_terminal_and_text_output() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+
+    let (status, output) = Running::spawn(bili_live(&server, dir.path()).args(["--tui", "--area", "235"]), "").finish();
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("--tui 需要在终端中运行"), "{}", output);
+
+    let (status, output) =
+        Running::spawn(bili_live(&server, dir.path()).args(["--tui", "--output", "json"]), "").finish();
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("--tui 不能与 --output json/env 或 --no-prompt 同时使用"), "{}", output);
+    assert_eq!(server.hits("/room/v1/Room/startLive"), 0);
+}
+
+#[test]
+fn recent_areas_are_offered_as_quick_picks() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.reply_once("/room/v1/Room/startLive", Reply::Error { code: -1, message: "stop here".into() });
+
+    let (_, output) = Running::spawn(&mut bili_live(&server, dir.path()), "x\n2\n").finish();
+
+    assert!(output.contains("1. 其他单机 - 235 (最近)"), "{}", output);
+    assert!(output.contains("2. 英雄联盟 - 86 (最近)"), "{}", output);
+    assert!(output.contains("无效的选择"), "{}", output);
+    let start = server.requests().into_iter().find(|r| r.path == "/room/v1/Room/startLive").unwrap();
+    assert!(start.body.contains("area_v2=86"), "{}", start.body);
+    assert_eq!(server.hits("/room/v1/Area/getList"), 0);
+
+    let (status, output) = Running::spawn(&mut bili_live(&server, dir.path()), "q\n").finish();
+    assert_eq!(status.code(), Some(1));
+    assert!(output.contains("已取消分区选择"), "{}", output);
+    assert_eq!(server.hits("/room/v1/Room/startLive"), 1);
+}
+
+#[test]
+fn favorites_work_on_a_fresh_install() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+
+    let added = Running::spawn(bili_live(&server, dir.path()).args(["areas", "favorite", "add", "235"]), "").wait();
+    assert!(added.status.success(), "{}", added.output);
+
+    let listed = Running::spawn(bili_live(&server, dir.path()).args(["areas", "favorite", "list"]), "").wait();
+    assert!(listed.output.contains("235 - 其他单机"), "{}", listed.output);
+}
+
+#[test]
+fn favorite_areas_are_listed_after_recent_ones() {
+    let server = MockServer::start();
+    let dir = TempDir::new().unwrap();
+    write_cookies(dir.path());
+    server.reply_once("/room/v1/Room/startLive", Reply::Error { code: -1, message: "stop here".into() });
+
+    for id in ["87", "86"] {
+        let added = Running::spawn(bili_live(&server, dir.path()).args(["areas", "favorite", "add", id]), "").wait();
+        assert!(added.status.success(), "{}", added.output);
+    }
+    let missing = Running::spawn(bili_live(&server, dir.path()).args(["areas", "favorite", "add", "1"]), "").wait();
+    assert!(!missing.status.success());
+    assert!(missing.output.contains("分区 1 不存在"), "{}", missing.output);
+    let typo =
+        Running::spawn(bili_live(&server, dir.path()).args(["--profile", "alcie", "areas", "favorite", "add", "86"]), "")
+            .wait();
+    assert!(typo.output.contains("账号 alcie 不存在"), "{}", typo.output);
+    let profiles = Running::spawn(bili_live(&server, dir.path()).args(["profile", "list"]), "").wait();
+    assert!(!profiles.output.contains("alcie"), "{}", profiles.output);
+
+    let listed =
+        Running::spawn(bili_live(&server, dir.path()).args(["areas", "favorite", "list", "--output", "env"]), "").wait();
+    assert!(listed.stdout.iter().any(|l| l == "BILI_LIVE_FAVORITE_AREAS='87,86'"), "{:?}", listed.stdout);
+
+    let (_, output) = Running::spawn(&mut bili_live(&server, dir.path()), "3\n").finish();
+    assert!(output.contains("2. 英雄联盟 - 86 (最近、收藏)"), "{}", output);
+    assert!(output.contains("3. 守望先锋 - 87 (收藏)"), "{}", output);
+    let start = server.requests().into_iter().find(|r| r.path == "/room/v1/Room/startLive").unwrap();
+    assert!(start.body.contains("area_v2=87"), "{}", start.body);
+
+    let removed = Running::spawn(bili_live(&server, dir.path()).args(["areas", "favorite", "remove", "87"]), "").wait();
+    assert!(removed.status.success(), "{}", removed.output);
+    let listed = Running::spawn(bili_live(&server, dir.path()).args(["areas", "favorite", "list"]), "").wait();
+    assert!(listed.output.contains("86 - 英雄联盟") && !listed.output.contains("87 - 守望先锋"), "{}", listed.output);
+}
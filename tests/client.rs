@@ -0,0 +1,229 @@
+mod common;
+
+use bili_live::{ApiBase, AreaListFetch, BiliLiveError, BilibiliClient, Cookies};
+use common::{MockServer, Reply};
+
+fn client(server: &MockServer) -> BilibiliClient {
+    BilibiliClient::new(ApiBase {
+        passport: server.url().to_string(),
+        web: server.url().to_string(),
+        live: server.url().to_string(),
+        www: server.url().to_string(),
+    })
+}
+
+fn logged_in_client(server: &MockServer) -> BilibiliClient {
+    let mut client = client(server);
+    let mut cookies = Cookies {
+        room_id: common::ROOM_ID,
+        refresh_token: common::REFRESH_TOKEN.to_string(),
+        ..Default::default()
+    };
+    cookies.set("SESSDATA", common::SESSDATA, None);
+    cookies.set("bili_jct", common::CSRF, None);
+    client.set_cookies(cookies);
+    client
+}
+
+#[test]
+fn qr_login_builds_cookies() {
+    let server = MockServer::start();
+    let mut client = client(&server);
+
+    let qr = client.generate_qr_code().unwrap();
+    let poll = client.poll_qr_code(&qr.qrcode_key).unwrap();
+    assert_eq!(poll.code, bili_live::QR_STATUS.success);
+
+    let cookies = client.login_with_poll(&poll).unwrap();
+    assert_eq!(cookies.room_id, common::ROOM_ID);
+    assert_eq!(cookies.sessdata(), common::SESSDATA);
+    assert_eq!(cookies.csrf_token(), common::CSRF);
+    assert_eq!(cookies.refresh_token, common::REFRESH_TOKEN);
+    assert!(client.is_logged_in().unwrap());
+}
+
+#[test]
+fn qr_login_keeps_every_cookie_with_expiry() {
+    let server = MockServer::start();
+    let mut client = client(&server);
+
+    let poll = client.poll_qr_code("mockqrcodekey").unwrap();
+    let cookies = client.login_with_poll(&poll).unwrap();
+
+    // Set-Cookie 中的过期时间优先于跨域地址中的 Expires
+    let expires = chrono::DateTime::parse_from_rfc3339("2030-01-01T00:00:00Z").unwrap();
+    assert_eq!(cookies.expires_at(), Some(expires.to_utc()));
+    assert_eq!(cookies.get("DedeUserID"), common::MID.to_string());
+    assert_eq!(cookies.get("DedeUserID__ckMd5"), "0123456789abcdef");
+    assert_eq!(cookies.cookies["DedeUserID__ckMd5"].expires.unwrap().timestamp(), 1893456000);
+    assert_eq!(cookies.get("sid"), common::SID);
+    assert_eq!(cookies.get("buvid3"), common::BUVID3);
+    assert!(!cookies.cookies.contains_key("gourl"));
+
+    let nav = server.requests().into_iter().rfind(|r| r.path == "/x/web-interface/nav").unwrap();
+    assert!(nav.cookie.contains(&format!("sid={}", common::SID)), "{}", nav.cookie);
+    assert!(nav.cookie.contains(&format!("buvid3={}", common::BUVID3)), "{}", nav.cookie);
+
+    // 无需登录的接口只携带设备标识
+    client.area_list().unwrap();
+    let areas = server.requests().into_iter().rfind(|r| r.path == "/room/v1/Area/getList").unwrap();
+    assert!(areas.cookie.contains(&format!("buvid3={}", common::BUVID3)), "{}", areas.cookie);
+    assert!(!areas.cookie.contains("SESSDATA"), "{}", areas.cookie);
+}
+
+#[test]
+fn expired_cookies_are_not_sent() {
+    let server = MockServer::start();
+    let mut client = logged_in_client(&server);
+    let mut cookies = client.cookies().unwrap().clone();
+    cookies.apply_set_cookie(&format!("SESSDATA={}; Expires=Thu, 01 Jan 2015 00:00:00 GMT", common::SESSDATA));
+    assert!(!cookies.cookies.contains_key("SESSDATA"));
+    cookies.set("SESSDATA", common::SESSDATA, chrono::DateTime::from_timestamp(1420070400, 0));
+    client.set_cookies(cookies);
+
+    assert!(!client.is_logged_in().unwrap());
+    let nav = server.requests().into_iter().rfind(|r| r.path == "/x/web-interface/nav").unwrap();
+    assert!(!nav.cookie.contains("SESSDATA"), "{}", nav.cookie);
+    assert!(nav.cookie.contains("bili_jct"), "{}", nav.cookie);
+}
+
+#[test]
+fn legacy_cookie_file_is_still_readable() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("cookies.json");
+    std::fs::write(&path, r#"{"room_id":4242,"sessdata":"legacy","csrf_token":"legacycsrf"}"#).unwrap();
+
+    let cookies = Cookies::load(&path).unwrap();
+    assert_eq!(cookies.room_id, 4242);
+    assert_eq!(cookies.sessdata(), "legacy");
+    assert_eq!(cookies.csrf_token(), "legacycsrf");
+    assert!(cookies.refresh_token.is_empty());
+
+    cookies.save(&path).unwrap();
+    let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved["cookies"]["SESSDATA"]["value"], "legacy");
+    assert!(saved.get("sessdata").is_none());
+}
+
+#[test]
+fn encrypted_cookie_file_round_trips() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("cookies.json");
+    let mut cookies = Cookies { room_id: 4242, ..Default::default() };
+    cookies.set("SESSDATA", "secretsess", None);
+
+    cookies.save_encrypted(&path, b"passphrase").unwrap();
+    assert!(Cookies::is_encrypted(&path).unwrap());
+    assert!(!std::fs::read_to_string(&path).unwrap().contains("secretsess"));
+
+    let loaded = Cookies::load_encrypted(&path, b"passphrase").unwrap();
+    assert_eq!(loaded.room_id, 4242);
+    assert_eq!(loaded.sessdata(), "secretsess");
+    assert!(matches!(Cookies::load_encrypted(&path, b"wrong"), Err(BiliLiveError::CryptoError(_))));
+    assert!(matches!(Cookies::load(&path), Err(BiliLiveError::CryptoError(_))));
+}
+
+#[test]
+fn refresh_replaces_expired_cookies() {
+    let server = MockServer::start();
+    let mut client = logged_in_client(&server);
+    server.expire_session();
+    assert!(!client.is_logged_in().unwrap());
+
+    let cookies = client.refresh_cookies().unwrap();
+    client.confirm_refresh(common::REFRESH_TOKEN).unwrap();
+    assert_eq!(cookies.room_id, common::ROOM_ID);
+    assert_eq!(cookies.sessdata(), common::REFRESHED_SESSDATA);
+    assert_eq!(cookies.csrf_token(), common::REFRESHED_CSRF);
+    assert_eq!(cookies.refresh_token, common::REFRESHED_TOKEN);
+    assert!(client.is_logged_in().unwrap());
+    assert_eq!(server.hits("/x/passport-login/web/confirm/refresh"), 1);
+    client.start_live(86).unwrap();
+}
+
+#[test]
+fn requests_without_cookies_are_rejected_locally() {
+    let server = MockServer::start();
+    let client = client(&server);
+
+    assert!(matches!(client.start_live(235), Err(BiliLiveError::CookieError)));
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+fn start_and_stop_live() {
+    let server = MockServer::start();
+    let client = logged_in_client(&server);
+
+    let live = client.start_live(86).unwrap();
+    assert_eq!(live.rtmp.addr, common::RTMP_ADDR);
+    assert_eq!(live.rtmp.code, common::RTMP_CODE);
+    assert_eq!(live.live_key, common::LIVE_KEY);
+    assert_eq!(server.live_area(), Some(86));
+
+    client.stop_live().unwrap();
+    assert!(!server.is_live());
+
+    let stats = client.live_stats(&live.live_key).unwrap();
+    assert_eq!(stats.danmu_num, 42);
+    assert_eq!(stats.live_time, 3600);
+}
+
+#[test]
+fn title_update_is_form_encoded() {
+    let server = MockServer::start();
+    let client = logged_in_client(&server);
+
+    client.update_title("100% 纯享+版").unwrap();
+
+    assert_eq!(server.title(), "100% 纯享+版");
+    let update = server.requests().into_iter().find(|r| r.path == "/room/v1/Room/update").unwrap();
+    assert!(update.body.contains(&format!("room_id={}", common::ROOM_ID)), "{}", update.body);
+    assert!(update.body.contains("title=100%25%20"), "{}", update.body);
+}
+
+#[test]
+fn area_ids_are_parsed_from_strings() {
+    let server = MockServer::start();
+    let client = logged_in_client(&server);
+
+    let areas = client.area_list().unwrap();
+    assert_eq!(areas[1].name, "单机游戏");
+    assert_eq!(areas[1].list[0].id, 235);
+
+    let recent = client.recent_areas().unwrap();
+    assert_eq!(recent.len(), 2);
+    assert_eq!(recent[1].id, 86);
+}
+
+#[test]
+fn area_list_is_revalidated_with_etag() {
+    let server = MockServer::start();
+    let client = client(&server);
+
+    let etag = match client.area_list_if_changed(None).unwrap() {
+        AreaListFetch::Updated { areas, etag } => {
+            assert_eq!(areas.len(), 2);
+            etag.unwrap()
+        }
+        AreaListFetch::NotModified => panic!("首次请求不应返回 304"),
+    };
+    assert_eq!(etag, common::AREA_ETAG);
+    assert!(matches!(client.area_list_if_changed(Some(&etag)).unwrap(), AreaListFetch::NotModified));
+    assert!(matches!(client.area_list_if_changed(Some("\"old\"")).unwrap(), AreaListFetch::Updated { .. }));
+}
+
+#[test]
+fn api_errors_carry_message_and_code() {
+    let server = MockServer::start();
+    let client = logged_in_client(&server);
+    server.reply_once("/room/v1/Room/stopLive", Reply::Error { code: 160000, message: "直播间未开播".into() });
+
+    match client.stop_live() {
+        Err(BiliLiveError::ApiError(message)) => {
+            assert!(message.contains("直播间未开播"));
+            assert!(message.contains("160000"));
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
@@ -0,0 +1,515 @@
+//! 集成测试使用的模拟B站接口服务
+#![allow(dead_code)]
+
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+pub const SESSDATA: &str = "mock%2Csessdata%2C1234";
+pub const CSRF: &str = "mockcsrf0123456789";
+pub const SID: &str = "mocksid";
+pub const BUVID3: &str = "MOCK-BUVID3-0000infoc";
+pub const REFRESH_TOKEN: &str = "mockrefreshtoken";
+pub const REFRESH_CSRF: &str = "mockrefreshcsrf";
+pub const REFRESHED_SESSDATA: &str = "mock%2Crefreshed%2C5678";
+pub const REFRESHED_CSRF: &str = "mockcsrfrefreshed0000";
+pub const REFRESHED_TOKEN: &str = "mockrefreshtoken2";
+pub const MID: i64 = 10086;
+pub const ROOM_ID: i64 = 4242;
+pub const LIVE_KEY: &str = "520520520520520";
+pub const RTMP_ADDR: &str = "rtmp://live-push.mock.local/live-bvc/";
+/// 分区列表的 ETag，请求携带相同的 If-None-Match 时返回 304
+pub const AREA_ETAG: &str = "\"mock-areas-v1\"";
+pub const COVER_URL: &str = "https://i0.hdslb.com/bfs/live/new_room_cover/mock.jpg";
+pub const RTMP_CODE: &str = "?streamname=live_10086_1234567&key=abcdef0123456789&schedule=rtmp";
+
+/// 二维码轮询接口依次返回的状态
+#[derive(Debug, Clone, Copy)]
+pub enum QrStep {
+    Waiting,
+    Scanned,
+    Success,
+    Expired,
+}
+
+/// 覆盖某个接口的默认响应
+#[derive(Debug, Clone)]
+pub enum Reply {
+    /// 返回 `code` 非零的错误响应
+    Error { code: i64, message: String },
+    /// 原样返回响应体，可用于模拟非法 JSON
+    Raw(String),
+    /// 返回任意 JSON
+    Json(Value),
+    /// 等待一段时间后再返回默认响应，用于模拟接口无响应
+    Delay(Duration),
+}
+
+/// 服务端记录下的请求
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub query: String,
+    pub cookie: String,
+    pub body: String,
+}
+
+#[derive(Default)]
+struct State {
+    qr_steps: VecDeque<QrStep>,
+    replies: HashMap<String, VecDeque<Reply>>,
+    requests: Vec<RecordedRequest>,
+    live: bool,
+    area_id: Option<u32>,
+    title: String,
+    /// 直播间公告、简介与以逗号分隔的标签
+    news: String,
+    description: String,
+    tags: String,
+    cover: Option<String>,
+    /// 当前有效的 SESSDATA、bili_jct 与 refresh_token
+    sessdata: String,
+    csrf: String,
+    refresh_token: String,
+    /// 等待确认刷新的旧 refresh_token
+    pending_refresh_token: Option<String>,
+    needs_refresh: bool,
+    /// 下一个响应附带的 Set-Cookie
+    set_cookies: Vec<String>,
+}
+
+pub struct MockServer {
+    server: Arc<tiny_http::Server>,
+    state: Arc<Mutex<State>>,
+    handle: Option<JoinHandle<()>>,
+    url: String,
+}
+
+impl MockServer {
+    pub fn start() -> Self {
+        let server = Arc::new(tiny_http::Server::http("127.0.0.1:0").expect("启动模拟服务失败"));
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let state = Arc::new(Mutex::new(State {
+            qr_steps: VecDeque::from([QrStep::Success]),
+            sessdata: SESSDATA.to_string(),
+            csrf: CSRF.to_string(),
+            refresh_token: REFRESH_TOKEN.to_string(),
+            title: "模拟直播间".to_string(),
+            news: "每晚八点开播".to_string(),
+            description: "这是一个模拟直播间".to_string(),
+            tags: "游戏,聊天".to_string(),
+            ..Default::default()
+        }));
+
+        let handle = {
+            let server = server.clone();
+            let state = state.clone();
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    handle_request(&state, request);
+                }
+            })
+        };
+
+        Self { server, state, handle: Some(handle), url }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// 设定二维码轮询接口依次返回的状态，最后一个状态会一直重复
+    pub fn script_qr(&self, steps: &[QrStep]) {
+        self.state.lock().unwrap().qr_steps = steps.iter().copied().collect();
+    }
+
+    /// 让指定接口的下一次请求返回给定响应，可多次调用以排队
+    pub fn reply_once(&self, path: &str, reply: Reply) {
+        self.state.lock().unwrap()
+            .replies
+            .entry(path.to_string())
+            .or_default()
+            .push_back(reply);
+    }
+
+    /// 使当前 SESSDATA 失效，只能通过 refresh_token 刷新
+    pub fn expire_session(&self) {
+        self.state.lock().unwrap().sessdata = "expired".to_string();
+    }
+
+    /// 让检查接口提示当前凭据需要刷新
+    pub fn require_refresh(&self) {
+        self.state.lock().unwrap().needs_refresh = true;
+    }
+
+    pub fn set_live(&self, live: bool) {
+        self.state.lock().unwrap().live = live;
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.state.lock().unwrap().live
+    }
+
+    /// 最近一次开播或切换时选择的分区
+    pub fn live_area(&self) -> Option<u32> {
+        self.state.lock().unwrap().area_id
+    }
+
+    /// 当前的直播间标题
+    pub fn title(&self) -> String {
+        self.state.lock().unwrap().title.clone()
+    }
+
+    /// 当前的直播间封面
+    pub fn cover(&self) -> Option<String> {
+        self.state.lock().unwrap().cover.clone()
+    }
+
+    /// 当前的直播间公告、简介与标签
+    pub fn room_profile(&self) -> (String, String, String) {
+        let state = self.state.lock().unwrap();
+        (state.news.clone(), state.description.clone(), state.tags.clone())
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// 指定路径被请求的次数
+    pub fn hits(&self, path: &str) -> usize {
+        self.requests().iter().filter(|r| r.path == path).count()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn ok(data: Value) -> Value {
+    json!({ "code": 0, "message": "0", "msg": "ok", "ttl": 1, "data": data })
+}
+
+fn error(code: i64, message: &str) -> Value {
+    json!({ "code": code, "message": message, "msg": message, "data": {} })
+}
+
+fn form_value(body: &str, key: &str) -> Option<String> {
+    body.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| v.to_string())
+}
+
+/// 解码表单值，`+` 视为空格
+fn percent_decode(value: &str) -> String {
+    let mut decoded = Vec::new();
+    let mut bytes = value.bytes();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'%' => {
+                let hex: String = bytes.by_ref().take(2).map(char::from).collect();
+                decoded.push(u8::from_str_radix(&hex, 16).expect("非法的百分号编码"));
+            }
+            b'+' => decoded.push(b' '),
+            b => decoded.push(b),
+        }
+    }
+    String::from_utf8(decoded).unwrap()
+}
+
+fn handle_request(state: &Mutex<State>, mut request: tiny_http::Request) {
+    let (path, query) = match request.url().split_once('?') {
+        Some((path, query)) => (path.to_string(), query.to_string()),
+        None => (request.url().to_string(), String::new()),
+    };
+    let header = |name: &'static str| {
+        request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv(name))
+            .map(|h| h.value.to_string())
+            .unwrap_or_default()
+    };
+    let cookie = header("Cookie");
+    let if_none_match = header("If-None-Match");
+    let mut bytes = Vec::new();
+    let _ = request.as_reader().read_to_end(&mut bytes);
+    // 上传的图片不是合法的 UTF-8，只用于检查表单字段
+    let body = String::from_utf8_lossy(&bytes).into_owned();
+
+    let recorded = RecordedRequest {
+        method: request.method().to_string(),
+        path: path.clone(),
+        query,
+        cookie,
+        body,
+    };
+
+    let scripted = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(recorded.clone());
+        state.replies.get_mut(&path).and_then(|q| q.pop_front())
+    };
+    if let Some(Reply::Delay(delay)) = scripted {
+        std::thread::sleep(delay);
+    }
+
+    let area_list = path == "/room/v1/Area/getList" && !matches!(scripted, Some(Reply::Error { .. } | Reply::Raw(_)));
+    if area_list && if_none_match == AREA_ETAG {
+        let _ = request.respond(tiny_http::Response::empty(304));
+        return;
+    }
+
+    let (response_body, set_cookies) = {
+        let mut state = state.lock().unwrap();
+        let body = match scripted {
+            Some(Reply::Error { code, message }) => error(code, &message).to_string(),
+            Some(Reply::Raw(raw)) => raw,
+            Some(Reply::Json(value)) => value.to_string(),
+            Some(Reply::Delay(_)) | None => match path.strip_prefix("/correspond/1/") {
+                Some(correspond_path) => correspond_page(correspond_path),
+                None => default_reply(&mut state, &recorded).to_string(),
+            },
+        };
+        (body, std::mem::take(&mut state.set_cookies))
+    };
+
+    let mut response = tiny_http::Response::from_string(response_body).with_header(
+        "Content-Type: application/json; charset=utf-8"
+            .parse::<tiny_http::Header>()
+            .unwrap(),
+    );
+    if area_list {
+        response.add_header(format!("ETag: {}", AREA_ETAG).parse::<tiny_http::Header>().unwrap());
+    }
+    for cookie in set_cookies {
+        response.add_header(format!("Set-Cookie: {}", cookie).parse::<tiny_http::Header>().unwrap());
+    }
+    let _ = request.respond(response);
+}
+
+/// 只接受 1024 位 RSA 密文对应的 256 位十六进制路径
+fn correspond_page(correspond_path: &str) -> String {
+    if correspond_path.len() == 256 && correspond_path.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+        format!(r#"<html><body><div id="1-name">{}</div></body></html>"#, REFRESH_CSRF)
+    } else {
+        "<html><body>404</body></html>".to_string()
+    }
+}
+
+fn default_reply(state: &mut State, request: &RecordedRequest) -> Value {
+    let logged_in = request.cookie.contains(&format!("SESSDATA={}", state.sessdata));
+    let csrf_ok = form_value(&request.body, "csrf") == Some(state.csrf.clone());
+
+    match request.path.as_str() {
+        "/x/passport-login/web/qrcode/generate" => ok(json!({
+            "url": "https://account.bilibili.com/h5/account-h5/auth/scan-web?navhide=1&qrcode_key=mockqrcodekey",
+            "qrcode_key": "mockqrcodekey",
+        })),
+        "/x/passport-login/web/qrcode/poll" => {
+            let step = if state.qr_steps.len() > 1 {
+                state.qr_steps.pop_front().unwrap()
+            } else {
+                state.qr_steps.front().copied().unwrap_or(QrStep::Waiting)
+            };
+            let (url, code, message) = match step {
+                QrStep::Waiting => (String::new(), 86101, "未扫码"),
+                QrStep::Scanned => (String::new(), 86090, "二维码已扫码未确认"),
+                QrStep::Expired => (String::new(), 86038, "二维码已失效"),
+                QrStep::Success => {
+                    state.set_cookies = vec![
+                        format!("SESSDATA={}; Path=/; Domain=bilibili.com; Expires=Tue, 01 Jan 2030 00:00:00 GMT; HttpOnly; Secure", SESSDATA),
+                        format!("bili_jct={}; Path=/; Domain=bilibili.com; Expires=Tue, 01 Jan 2030 00:00:00 GMT", CSRF),
+                        format!("DedeUserID={}; Path=/; Domain=bilibili.com; Expires=Tue, 01 Jan 2030 00:00:00 GMT", MID),
+                        format!("sid={}; Path=/; Domain=bilibili.com; Expires=Tue, 01 Jan 2030 00:00:00 GMT", SID),
+                    ];
+                    let url = format!(
+                        "https://passport.biligame.com/x/passport-login/web/crossDomain?DedeUserID={}&DedeUserID__ckMd5=0123456789abcdef&Expires=1893456000&SESSDATA={}&bili_jct={}&gourl=https%3A%2F%2Fwww.bilibili.com",
+                        MID, SESSDATA, CSRF
+                    );
+                    (url, 0, "")
+                }
+            };
+            ok(json!({ "url": url, "refresh_token": REFRESH_TOKEN, "timestamp": 0, "code": code, "message": message }))
+        }
+        "/x/frontend/finger/spi" => ok(json!({ "b_3": BUVID3, "b_4": "MOCK-BUVID4-0000" })),
+        "/x/passport-login/web/cookie/info" => {
+            if !logged_in {
+                return error(-101, "账号未登录");
+            }
+            ok(json!({ "refresh": state.needs_refresh, "timestamp": 1735689600000i64 }))
+        }
+        "/x/passport-login/web/cookie/refresh" => {
+            if !request.cookie.contains("SESSDATA=")
+                || !csrf_ok
+                || form_value(&request.body, "refresh_csrf").as_deref() != Some(REFRESH_CSRF)
+                || form_value(&request.body, "refresh_token") != Some(state.refresh_token.clone())
+            {
+                return error(-111, "csrf 校验失败");
+            }
+            state.pending_refresh_token = Some(state.refresh_token.clone());
+            state.sessdata = REFRESHED_SESSDATA.to_string();
+            state.csrf = REFRESHED_CSRF.to_string();
+            state.refresh_token = REFRESHED_TOKEN.to_string();
+            state.needs_refresh = false;
+            state.set_cookies = vec![
+                format!("SESSDATA={}; Path=/; Domain=bilibili.com; HttpOnly; Secure", REFRESHED_SESSDATA),
+                format!("bili_jct={}; Path=/; Domain=bilibili.com", REFRESHED_CSRF),
+                format!("DedeUserID={}; Path=/; Domain=bilibili.com", MID),
+            ];
+            ok(json!({ "status": 0, "message": "", "refresh_token": REFRESHED_TOKEN }))
+        }
+        "/x/passport-login/web/confirm/refresh" => {
+            if !logged_in || !csrf_ok || form_value(&request.body, "refresh_token") != state.pending_refresh_token {
+                return error(-111, "csrf 校验失败");
+            }
+            state.pending_refresh_token = None;
+            ok(json!({}))
+        }
+        "/login/exit/v2" => {
+            if !logged_in || form_value(&request.body, "biliCSRF") != Some(state.csrf.clone()) {
+                return error(2202, "csrf 请求非法");
+            }
+            ok(json!({ "redirectUrl": "https://www.bilibili.com" }))
+        }
+        "/x/web-interface/nav" => {
+            if logged_in {
+                ok(json!({ "isLogin": true, "mid": MID, "uname": "mock_user" }))
+            } else {
+                json!({ "code": -101, "message": "账号未登录", "ttl": 1, "data": { "isLogin": false } })
+            }
+        }
+        "/room/v1/Room/getRoomInfoOld" => ok(json!({
+            "roomStatus": 1,
+            "roundStatus": 0,
+            "liveStatus": if state.live { 1 } else { 0 },
+            "url": format!("https://live.bilibili.com/{}", ROOM_ID),
+            "title": state.title,
+            "cover": "",
+            "online": 0,
+            "roomid": ROOM_ID,
+            "broadcast_type": 0,
+            "online_hidden": 0,
+        })),
+        "/room/v1/Area/getList" => ok(json!([
+            {
+                "id": 2,
+                "name": "网游",
+                "list": [
+                    { "id": "86", "parent_id": "2", "name": "英雄联盟" },
+                    { "id": "87", "parent_id": "2", "name": "守望先锋" },
+                ],
+            },
+            {
+                "id": 6,
+                "name": "单机游戏",
+                "list": [
+                    { "id": "235", "parent_id": "6", "name": "其他单机" },
+                    { "id": "236", "parent_id": "6", "name": "主机游戏" },
+                ],
+            },
+        ])),
+        "/room/v1/Area/getMyChooseArea" => ok(json!([
+            { "id": "235", "name": "其他单机", "parent_id": "6", "parent_name": "单机游戏" },
+            { "id": "86", "name": "英雄联盟", "parent_id": "2", "parent_name": "网游" },
+        ])),
+        "/room/v1/Room/startLive" => {
+            if !logged_in || !csrf_ok {
+                return error(-111, "csrf 校验失败");
+            }
+            state.live = true;
+            state.area_id = form_value(&request.body, "area_v2").and_then(|id| id.parse().ok());
+            ok(json!({
+                "change": 1,
+                "status": "LIVE",
+                "room_type": 0,
+                "rtmp": { "addr": RTMP_ADDR, "code": RTMP_CODE, "new_link": "", "provider": "live" },
+                "protocols": [],
+                "try_time": "0000-00-00 00:00:00",
+                "live_key": LIVE_KEY,
+                "sub_session_key": format!("{}sub_time:0", LIVE_KEY),
+                "notice": {},
+            }))
+        }
+        "/room/v1/Room/update" => {
+            if !logged_in || !csrf_ok || form_value(&request.body, "csrf_token") != Some(state.csrf.clone()) {
+                return error(-111, "csrf 校验失败");
+            }
+            if let Some(title) = form_value(&request.body, "title") {
+                state.title = percent_decode(&title);
+            }
+            if let Some(description) = form_value(&request.body, "description") {
+                state.description = percent_decode(&description);
+            }
+            if let Some(tags) = form_value(&request.body, "tags") {
+                state.tags = percent_decode(&tags);
+            }
+            if let Some(area_id) = form_value(&request.body, "area_id") {
+                state.area_id = area_id.parse().ok();
+            }
+            ok(json!({}))
+        }
+        "/x/upload/web/image" => {
+            let csrf = request.query.strip_prefix("csrf=").unwrap_or_default();
+            if !logged_in || csrf != state.csrf || !request.body.contains("name=\"bucket\"\r\n\r\nlive") {
+                return error(-111, "csrf 校验失败");
+            }
+            if !request.body.contains("Content-Type: image/jpeg") {
+                return error(-400, "请求错误");
+            }
+            ok(json!({ "location": COVER_URL, "etag": "mocketag" }))
+        }
+        "/room/v1/Cover/new_replace_cover" => {
+            if !logged_in || !csrf_ok {
+                return error(-111, "csrf 校验失败");
+            }
+            state.cover = form_value(&request.body, "url").map(|url| percent_decode(&url));
+            ok(json!({}))
+        }
+        "/room/v1/Room/get_info" => ok(json!({
+            "uid": MID,
+            "room_id": ROOM_ID,
+            "title": state.title,
+            "description": state.description,
+            "tags": state.tags,
+            "live_status": if state.live { 1 } else { 0 },
+        })),
+        "/room_ex/v1/RoomNews/get" => ok(json!({
+            "roomid": ROOM_ID.to_string(),
+            "uid": MID.to_string(),
+            "content": state.news,
+        })),
+        "/xlive/app-blink/v1/index/updateRoomNews" => {
+            if !logged_in || !csrf_ok || form_value(&request.body, "uid") != Some(MID.to_string()) {
+                return error(-111, "csrf 校验失败");
+            }
+            state.news = percent_decode(&form_value(&request.body, "content").unwrap_or_default());
+            ok(json!({}))
+        }
+        "/room/v1/Room/stopLive" => {
+            if !logged_in || !csrf_ok {
+                return error(-111, "csrf 校验失败");
+            }
+            state.live = false;
+            ok(json!({ "change": 1, "status": "PREPARING" }))
+        }
+        "/xlive/app-blink/v1/live/StopLiveData" => ok(json!({
+            "AddFans": 3,
+            "HamsterRmb": 0,
+            "NewFansClub": 1,
+            "DanmuNum": 42,
+            "MaxOnline": 17,
+            "WatchedCount": 128,
+            "LiveTime": 3600,
+        })),
+        _ => error(-404, "啥都木有"),
+    }
+}